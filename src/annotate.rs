@@ -0,0 +1,152 @@
+// src/annotate.rs
+
+use druid::kurbo::Line;
+use druid::piet::{LineCap, StrokeStyle};
+use druid::{Color, Data, PaintCtx, Point, Rect, RenderContext, Vec2};
+use image::{ImageBuffer, Rgba};
+
+/// 标注线条的宽度（逻辑坐标）
+const STROKE_WIDTH: f64 = 3.0;
+/// 箭头两翼的长度（逻辑坐标）
+const ARROW_HEAD_LENGTH: f64 = 14.0;
+/// 箭头两翼与箭杆的夹角（弧度）
+const ARROW_HEAD_ANGLE: f64 = 0.45;
+/// 标注颜色（红色）
+const COLOR: (u8, u8, u8) = (230, 30, 30);
+
+/// 绘制标注的工具
+#[derive(Clone, Copy, Debug, PartialEq, Data)]
+pub enum Tool {
+    Rect,     // 矩形框
+    Arrow,    // 箭头
+    Freehand, // 自由画笔
+    Mosaic,   // 马赛克
+}
+
+/// 画在截图上的一个标注，坐标与选区一样使用截图的逻辑坐标（不随窗口的缩放和平移变化）
+#[derive(Clone, Debug, PartialEq)]
+pub enum Annotation {
+    /// `(x0, y0)` 为拖动起点、`(x1, y1)` 为终点，未规范化
+    Rect(Rect),
+    Arrow { from: Point, to: Point },
+    Freehand(Vec<Point>),
+    /// 打马赛克的区域，与 `Rect` 一样未规范化；不画线条，由 `redact` 模块处理像素
+    Mosaic(Rect),
+}
+
+impl Annotation {
+    /// 用 `tool` 在 `pos` 处开始一个新标注
+    pub fn start(tool: Tool, pos: Point) -> Self {
+        match tool {
+            Tool::Rect => Annotation::Rect(Rect::new(pos.x, pos.y, pos.x, pos.y)),
+            Tool::Arrow => Annotation::Arrow { from: pos, to: pos },
+            Tool::Freehand => Annotation::Freehand(vec![pos]),
+            Tool::Mosaic => Annotation::Mosaic(Rect::new(pos.x, pos.y, pos.x, pos.y)),
+        }
+    }
+
+    /// 拖动到 `pos`：矩形和箭头移动终点，画笔追加一个点
+    pub fn extend_to(&mut self, pos: Point) {
+        match self {
+            Annotation::Rect(rect) | Annotation::Mosaic(rect) => *rect = Rect::new(rect.x0, rect.y0, pos.x, pos.y),
+            Annotation::Arrow { to, .. } => *to = pos,
+            Annotation::Freehand(points) => points.push(pos),
+        }
+    }
+
+    /// 是否太小，只是一次单击而没有画出东西
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Annotation::Rect(rect) | Annotation::Mosaic(rect) => {
+                rect.width().abs() < 1.0 || rect.height().abs() < 1.0
+            }
+            Annotation::Arrow { from, to } => (*to - *from).hypot() < 1.0,
+            Annotation::Freehand(points) => points.len() < 2,
+        }
+    }
+
+    /// 标注分解成的线段；屏幕绘制和导出共用，保证两者一致
+    fn segments(&self) -> Vec<Line> {
+        match self {
+            Annotation::Rect(rect) => {
+                let r = rect.abs();
+                let corners = [
+                    Point::new(r.x0, r.y0),
+                    Point::new(r.x1, r.y0),
+                    Point::new(r.x1, r.y1),
+                    Point::new(r.x0, r.y1),
+                ];
+                (0..4).map(|i| Line::new(corners[i], corners[(i + 1) % 4])).collect()
+            }
+            Annotation::Arrow { from, to } => {
+                let back = *from - *to;
+                let length = back.hypot();
+                let mut lines = vec![Line::new(*from, *to)];
+                if length > 0.0 {
+                    let back = back / length * ARROW_HEAD_LENGTH.min(length);
+                    for angle in [ARROW_HEAD_ANGLE, -ARROW_HEAD_ANGLE] {
+                        let (sin, cos) = angle.sin_cos();
+                        let wing = Vec2::new(back.x * cos - back.y * sin, back.x * sin + back.y * cos);
+                        lines.push(Line::new(*to, *to + wing));
+                    }
+                }
+                lines
+            }
+            Annotation::Freehand(points) => points.windows(2).map(|w| Line::new(w[0], w[1])).collect(),
+            Annotation::Mosaic(_) => Vec::new(),
+        }
+    }
+
+    /// 马赛克区域（已规范化），其他标注返回 `None`
+    pub fn mosaic_region(&self) -> Option<Rect> {
+        match self {
+            Annotation::Mosaic(rect) => Some(rect.abs()),
+            _ => None,
+        }
+    }
+}
+
+/// 在屏幕上绘制标注
+pub fn draw(ctx: &mut PaintCtx, annotation: &Annotation) {
+    let color = Color::rgb8(COLOR.0, COLOR.1, COLOR.2);
+    // 圆头线段拼接起来拐角也是圆的，与导出时的效果一致
+    let style = StrokeStyle::new().line_cap(LineCap::Round);
+    for line in annotation.segments() {
+        ctx.stroke_styled(line, &color, STROKE_WIDTH, &style);
+    }
+}
+
+/// 把标注画进裁剪出的图像中
+///
+/// `offset` 为图像左上角在截图中的像素坐标，`scale` 为显示器缩放比例，
+/// 与 `pixel_region` 的换算一致：截图像素 = 逻辑坐标 × `scale`。
+pub fn bake(img: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, annotations: &[Annotation], offset: Vec2, scale: f64) {
+    let to_pixels = |p: Point| Point::new(p.x * scale, p.y * scale) - offset;
+    let radius = STROKE_WIDTH * scale / 2.0;
+    for line in annotations.iter().flat_map(Annotation::segments) {
+        stamp_segment(img, to_pixels(line.p0), to_pixels(line.p1), radius);
+    }
+}
+
+/// 把距线段 `a`-`b` 不超过 `radius` 的像素涂成标注颜色（即圆头粗线）
+fn stamp_segment(img: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, a: Point, b: Point, radius: f64) {
+    let (w, h) = img.dimensions();
+    let bounds = Rect::from_points(a, b).inflate(radius, radius);
+    let x0 = bounds.x0.floor().max(0.0) as u32;
+    let y0 = bounds.y0.floor().max(0.0) as u32;
+    let x1 = (bounds.x1.ceil().max(0.0) as u32).min(w);
+    let y1 = (bounds.y1.ceil().max(0.0) as u32).min(h);
+
+    let ab = b - a;
+    let len2 = ab.hypot2();
+    for y in y0..y1 {
+        for x in x0..x1 {
+            // 以像素中心计算到线段的距离
+            let p = Point::new(x as f64 + 0.5, y as f64 + 0.5);
+            let t = if len2 > 0.0 { ((p - a).dot(ab) / len2).clamp(0.0, 1.0) } else { 0.0 };
+            if (p - (a + ab * t)).hypot() <= radius {
+                img.put_pixel(x, y, Rgba([COLOR.0, COLOR.1, COLOR.2, 255]));
+            }
+        }
+    }
+}
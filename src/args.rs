@@ -0,0 +1,89 @@
+// src/args.rs
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+
+/// 命令行选项；不带参数时与以前的行为一致
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub delay: Option<Duration>,                  // --delay <秒>：等待一段时间后再截图
+    pub monitor: Option<usize>,                   // --monitor <序号>：指定截取的显示器（从 0 开始）
+    pub scroll: bool,                             // --scroll：滚动截图，把滚动过程中的多帧拼成长图
+    pub active_window: bool,                      // --active-window：只截取当前活动的窗口
+    pub region_rel: Option<(u32, u32, u32, u32)>, // --region-rel x,y,w,h：活动窗口中相对其左上角的像素区域
+}
+
+/// 用法说明，参数有误时附在错误信息后面
+const USAGE: &str = "用法: rsqs [--delay <秒>] [--monitor <序号>] [--scroll] [--active-window [--region-rel <x,y,w,h>]]";
+
+/// 解析命令行参数（不含程序名），例如 `parse_args(std::env::args().skip(1))`
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| anyhow!("{} 缺少参数值", name));
+        match arg.as_str() {
+            "--delay" => {
+                let secs: f64 = value("--delay")?.parse()?;
+                options.delay = Some(Duration::try_from_secs_f64(secs)?);
+            }
+            "--monitor" => options.monitor = Some(value("--monitor")?.parse()?),
+            "--scroll" => options.scroll = true,
+            "--active-window" => options.active_window = true,
+            "--region-rel" => options.region_rel = Some(parse_region(&value("--region-rel")?)?),
+            _ => return Err(anyhow!("未知参数: {}\n{}", arg, USAGE)),
+        }
+    }
+    if options.region_rel.is_some() && !options.active_window {
+        return Err(anyhow!("--region-rel 需要与 --active-window 一起使用\n{}", USAGE));
+    }
+    if options.active_window && options.scroll {
+        return Err(anyhow!("--active-window 不能与 --scroll 同时使用，滚动截图本来就只截取活动窗口"));
+    }
+    Ok(options)
+}
+
+/// 解析 `x,y,w,h` 形式的区域
+fn parse_region(text: &str) -> Result<(u32, u32, u32, u32)> {
+    let parts = text
+        .split(',')
+        .map(|part| part.trim().parse::<u32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow!("--region-rel 的格式应为 x,y,w,h: {}", e))?;
+    match parts[..] {
+        [x, y, w, h] => Ok((x, y, w, h)),
+        _ => Err(anyhow!("--region-rel 的格式应为 x,y,w,h，收到 {} 个数", parts.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn parses_all_options() {
+        let options = parse(&["--delay", "1.5", "--monitor", "1", "--scroll"]).unwrap();
+        assert_eq!(
+            options,
+            Options { delay: Some(Duration::from_millis(1500)), monitor: Some(1), scroll: true, ..Options::default() }
+        );
+        let options = parse(&["--active-window", "--region-rel", "10, 20,300,200"]).unwrap();
+        assert!(options.active_window);
+        assert_eq!(options.region_rel, Some((10, 20, 300, 200)));
+        assert_eq!(parse(&[]).unwrap(), Options::default());
+    }
+
+    #[test]
+    fn rejects_unknown_or_incomplete_options() {
+        assert!(parse(&["--monitor"]).is_err());
+        assert!(parse(&["--delay", "-1"]).is_err());
+        assert!(parse(&["--full"]).is_err());
+        assert!(parse(&["--active-window", "--region-rel", "10,20,300"]).is_err());
+        assert!(parse(&["--region-rel", "10,20,300,200"]).is_err());
+    }
+}
@@ -0,0 +1,16 @@
+// src/bin/rsqs-control.rs
+//
+// 启动 rsqs 本地控制接口，协议见 src/control.rs。
+
+use anyhow::{Result, anyhow};
+use rsqs::control::{default_address, serve};
+
+fn main() -> Result<()> {
+    // 可选的第一个参数为套接字路径（Unix）或管道名（Windows）
+    let address = match std::env::args().nth(1) {
+        Some(address) => address,
+        None => default_address().ok_or_else(|| anyhow!("找不到默认的套接字目录，请在参数中指定路径"))?,
+    };
+    eprintln!("rsqs 控制接口监听于 {}", address);
+    serve(&address)
+}
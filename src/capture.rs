@@ -0,0 +1,238 @@
+// src/capture.rs
+
+use std::time::Duration;
+
+use anyhow::Result;
+use druid::Point;
+use image::DynamicImage;
+use mouse_position::mouse_position::Mouse;
+use xcap::{Monitor, Window};
+
+use crate::crop::crop_exact;
+use crate::stitch::stitch_vertical;
+
+/// 系统截图接口调用失败时附带的提示；只有这类错误才与屏幕录制权限有关
+const PERMISSION_HINT: &str = "请检查是否授予了屏幕录制权限（macOS：系统设置 → 隐私与安全性 → 屏幕录制）";
+
+/// 截取指定显示器的画面。
+///
+/// xcap 依赖的是另一个版本的 image crate，这里通过原始字节转换成本项目使用的 `DynamicImage`。
+pub fn capture_monitor(monitor: &Monitor) -> Result<DynamicImage> {
+    let img = monitor
+        .capture_image()
+        .map_err(|e| anyhow::anyhow!("系统截图接口调用失败: {}\n{}", e, PERMISSION_HINT))?;
+    from_xcap(img.width(), img.height(), img.into_raw())
+}
+
+/// 截取主显示器；如果系统没有标记主显示器，则使用第一个显示器。
+pub fn capture_primary_monitor() -> Result<DynamicImage> {
+    let mons = Monitor::all()?;
+    let mon = mons
+        .iter()
+        .find(|m| m.is_primary().unwrap_or(false))
+        .or_else(|| mons.first())
+        .ok_or_else(|| anyhow::anyhow!("找不到显示器"))?;
+    capture_monitor(mon)
+}
+
+/// 截取指定窗口的画面（只包含窗口本身，不含其他窗口和任务栏）。
+pub fn capture_window(window: &Window) -> Result<DynamicImage> {
+    let img = window
+        .capture_image()
+        .map_err(|e| anyhow::anyhow!("窗口截图失败: {}\n{}", e, PERMISSION_HINT))?;
+    from_xcap(img.width(), img.height(), img.into_raw())
+}
+
+/// 当前获得焦点的窗口；已最小化的窗口不算。
+pub fn active_window() -> Result<Window> {
+    Window::all()?
+        .into_iter()
+        .find(|w| w.is_focused().unwrap_or(false) && !w.is_minimized().unwrap_or(false))
+        .ok_or_else(|| anyhow::anyhow!("找不到当前活动的窗口"))
+}
+
+/// 当前获得焦点的窗口所属程序的名称；找不到时返回 `None`。
+pub fn active_app_name() -> Option<String> {
+    active_window().ok()?.app_name().ok()
+}
+
+/// 截取当前获得焦点的窗口。
+pub fn capture_active_window() -> Result<DynamicImage> {
+    capture_window(&active_window()?)
+}
+
+/// 截取当前获得焦点的窗口中 `(x, y, w, h)` 的区域（相对窗口左上角的像素坐标）。
+///
+/// 结果与窗口在屏幕上的位置无关，适合反复截取同一个程序的同一块面板。
+/// 区域必须完全落在窗口内，否则返回错误。
+pub fn capture_active_window_region(region: (u32, u32, u32, u32)) -> Result<DynamicImage> {
+    crop_exact(&capture_active_window()?, region).map_err(|e| anyhow::anyhow!("相对窗口的{}", e))
+}
+
+/// 选择并截取目标显示器：优先指定的显示器，未指定时优先鼠标所在的显示器，
+/// 失败时按顺序尝试其余显示器
+///
+/// # 返回
+/// 截图、该显示器左上角在虚拟桌面中的逻辑坐标，以及该显示器的缩放比例。
+pub fn capture_target(monitors: &[Monitor], index: Option<usize>) -> Result<(DynamicImage, Point, f64)> {
+    if monitors.is_empty() {
+        return Err(anyhow::anyhow!("找不到显示器"));
+    }
+
+    let under_cursor = match Mouse::get_mouse_position() {
+        Mouse::Position { x, y } if index.is_none() => Monitor::from_point(x, y).ok(),
+        _ => None,
+    };
+    let preferred = match index {
+        Some(i) => Some(monitors.get(i).ok_or_else(|| {
+            anyhow::anyhow!(
+                "显示器序号 {} 超出范围：共 {} 个显示器，--monitor 可用 0 到 {}",
+                i,
+                monitors.len(),
+                monitors.len() - 1
+            )
+        })?),
+        None => under_cursor.as_ref(),
+    };
+    // 其余显示器按系统顺序排在后面，并跳过已经作为首选的那个
+    let preferred_id = preferred.and_then(|m| m.id().ok());
+    let candidates = preferred
+        .into_iter()
+        .chain(monitors.iter().filter(|m| preferred_id.is_none() || m.id().ok() != preferred_id));
+
+    let try_capture = |mon: &Monitor| -> Result<(DynamicImage, Point, f64)> {
+        let scale = mon.scale_factor().map(f64::from).ok().filter(|s| *s > 0.0).unwrap_or(1.0);
+        // xcap 在 macOS 上返回的位置已经是逻辑坐标，其他平台上是物理像素
+        let origin = Point::new(mon.x()? as f64, mon.y()? as f64);
+        let origin = if cfg!(target_os = "macos") {
+            origin
+        } else {
+            Point::new(origin.x / scale, origin.y / scale)
+        };
+        Ok((capture_monitor(mon)?, origin, scale))
+    };
+    // 记下每个显示器失败的原因，全部失败时一并报告
+    let mut failures = Vec::new();
+    for mon in candidates {
+        match try_capture(mon) {
+            Ok(target) => return Ok(target),
+            Err(e) => {
+                let name = mon.name().unwrap_or_default();
+                eprintln!("显示器 {} 截图失败: {}", name, e);
+                failures.push(format!("{}: {}", name, e));
+            }
+        }
+    }
+    Err(anyhow::anyhow!("所有显示器都截图失败\n{}", failures.join("\n")))
+}
+
+/// 滚动截图时两帧之间的间隔
+const SCROLL_INTERVAL: Duration = Duration::from_millis(1200);
+/// 滚动截图最多截取的帧数
+const SCROLL_MAX_FRAMES: usize = 30;
+/// 开始滚动前最多等待的帧数（约 10 秒），期间画面不变不会结束录制
+const SCROLL_START_FRAMES: usize = 8;
+/// 开始滚动后，连续这么多帧不变才认为用户停止了滚动（约 2.4 秒）
+const SCROLL_IDLE_FRAMES: usize = 2;
+
+/// 滚动截图：截取当前活动的窗口，之后每隔 `SCROLL_INTERVAL` 再截一帧，
+/// 用户停止滚动或达到帧数上限后拼接成长图
+///
+/// 画面第一次变化前最多等待 `SCROLL_START_FRAMES` 帧，之后连续 `SCROLL_IDLE_FRAMES`
+/// 帧不变才结束，滚动中途稍作停顿不会提前结束。录制期间没有提示窗口（它会出现在截图里）。
+///
+/// 只截窗口本身，任务栏和其他窗口不会出现在每一段的接缝处；
+/// 窗口的标题栏、工具栏等固定区域由 `stitch_vertical` 识别，只保留一份。
+/// 找不到活动窗口时改为截取 `first` 所在的显示器（与 [`capture_target`] 的参数相同）。
+pub fn capture_scrolling(first: DynamicImage, monitors: &[Monitor], index: Option<usize>) -> Result<DynamicImage> {
+    let window = active_window().inspect_err(|e| eprintln!("{}，改为截取整个显示器", e)).ok();
+    let capture = || match &window {
+        Some(window) => capture_window(window),
+        None => capture_target(monitors, index).map(|(frame, _, _)| frame),
+    };
+    let first = match &window {
+        Some(_) => capture()?,
+        None => first,
+    };
+    let frames = record_scrolling(first, capture, || std::thread::sleep(SCROLL_INTERVAL))?;
+    stitch_vertical(&frames)
+}
+
+/// 录制滚动过程中内容不同的各帧：每次先调用 `wait`，再用 `capture` 截一帧
+fn record_scrolling(
+    first: DynamicImage,
+    mut capture: impl FnMut() -> Result<DynamicImage>,
+    mut wait: impl FnMut(),
+) -> Result<Vec<DynamicImage>> {
+    let mut frames = vec![first];
+    let mut unchanged = 0;
+    while frames.len() < SCROLL_MAX_FRAMES {
+        wait();
+        let frame = capture()?;
+        if frames.last().is_some_and(|last| last.as_bytes() == frame.as_bytes()) {
+            unchanged += 1;
+            let limit = if frames.len() == 1 { SCROLL_START_FRAMES } else { SCROLL_IDLE_FRAMES };
+            if unchanged >= limit {
+                break;
+            }
+            continue;
+        }
+        unchanged = 0;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// 把 xcap 返回的 RGBA 字节转换成 `DynamicImage`
+fn from_xcap(w: u32, h: u32, raw: Vec<u8>) -> Result<DynamicImage> {
+    let len = raw.len();
+    let buf = image::ImageBuffer::from_raw(w, h, raw).ok_or_else(|| {
+        anyhow::anyhow!("截图数据无法转换为图像：{}x{} 的画面只收到 {} 字节，可能是显卡驱动或截图接口异常", w, h, len)
+    })?;
+    Ok(DynamicImage::ImageRgba8(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 纯色的一帧，`shade` 不同的两帧内容不同
+    fn frame(shade: u8) -> DynamicImage {
+        DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(4, 4, image::Rgba([shade, shade, shade, 255])))
+    }
+
+    /// 按 `shades` 的顺序依次返回各帧，返回录制到的帧和截图次数
+    fn record(shades: &[u8]) -> (Vec<u8>, usize) {
+        let mut calls = 0;
+        let frames = record_scrolling(
+            frame(shades[0]),
+            || {
+                calls += 1;
+                Ok(frame(shades[calls.min(shades.len() - 1)]))
+            },
+            || {},
+        )
+        .unwrap();
+        (frames.iter().map(|f| f.as_bytes()[0]).collect(), calls)
+    }
+
+    #[test]
+    fn waits_for_the_first_scroll() {
+        // 前 5 帧都没有变化，之后才开始滚动
+        let (frames, _) = record(&[0, 0, 0, 0, 0, 0, 1, 2, 2, 2]);
+        assert_eq!(frames, [0, 1, 2]);
+    }
+
+    #[test]
+    fn short_pause_while_scrolling_does_not_stop() {
+        let (frames, _) = record(&[0, 1, 1, 2, 3, 3, 3]);
+        assert_eq!(frames, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn gives_up_when_nothing_scrolls() {
+        let (frames, calls) = record(&[0]);
+        assert_eq!(frames, [0]);
+        assert_eq!(calls, SCROLL_START_FRAMES);
+    }
+}
@@ -0,0 +1,64 @@
+// src/clipboard.rs
+
+use anyhow::Result;
+use arboard::{Clipboard, ImageData};
+use image::{ImageBuffer, Rgba};
+
+/// 将 RGBA 字节转换为预乘 alpha 形式（四舍五入）
+fn premultiply_alpha(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .chunks_exact(4)
+        .flat_map(|px| {
+            let a = px[3] as u16;
+            let mul = |c: u8| ((c as u16 * a + 127) / 255) as u8;
+            [mul(px[0]), mul(px[1]), mul(px[2]), px[3]]
+        })
+        .collect()
+}
+
+/// 将图片复制到剪贴板
+///
+/// `premultiply` 为 `true` 时先把颜色按透明度预乘。arboard 在 Windows 上同时写入
+/// CF_DIBV5 位图和 PNG 两种格式，两者用的是同一份字节：按预乘 alpha 读取位图的程序
+/// 需要预乘后的数据，否则半透明边缘发黑；读取 PNG 的程序（浏览器、多数图片编辑器）
+/// 却会把预乘后的数据当成普通 RGBA，边缘反而变暗。两者无法同时满足，因此默认应传 `false`，
+/// 只在常用的程序读取位图、粘贴后边缘发黑时开启。macOS 和 Linux 上只写入非预乘的 PNG，不应开启。
+/// 完全不透明的图像两种方式结果相同。
+pub fn copy_image_to_clipboard(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, premultiply: bool) -> Result<()> {
+    let mut clipboard = Clipboard::new()?;
+    let bytes = if premultiply {
+        premultiply_alpha(image.as_raw()).into()
+    } else {
+        image.as_raw().into()
+    };
+    let image_data = ImageData {
+        width: image.width() as usize,
+        height: image.height() as usize,
+        bytes,
+    };
+    clipboard.set_image(image_data)?;
+    Ok(())
+}
+
+/// 将文本复制到剪贴板
+pub fn copy_text_to_clipboard(text: &str) -> Result<()> {
+    let mut clipboard = Clipboard::new()?;
+    clipboard.set_text(text.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn premultiplies_semi_transparent_pixel() {
+        assert_eq!(premultiply_alpha(&[200, 100, 50, 128]), [100, 50, 25, 128]);
+    }
+
+    #[test]
+    fn premultiply_keeps_opaque_and_clears_transparent() {
+        assert_eq!(premultiply_alpha(&[200, 100, 50, 255]), [200, 100, 50, 255]);
+        assert_eq!(premultiply_alpha(&[200, 100, 50, 0]), [0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,127 @@
+// src/config.rs
+//
+// 用户配置，保存在配置目录下的 `rsqs/config.json`，启动时读取一次。
+// 文件不存在时使用默认配置；所有字段都可以省略。
+//
+//     {
+//         "filters": [{"name": "grayscale"}, {"name": "brightness", "value": 10}],
+//         "reveal_after_save": true,
+//         "interpolation": "bilinear",
+//         "premultiply_clipboard": false,
+//         "safe_areas": [{"name": "Banner 3:1", "w": 3, "h": 1}],
+//         "handle_size": 12.0,
+//         "handle_tolerance": 8.0,
+//         "save_name": "{app}/{date}_{counter}"
+//     }
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow};
+use druid::piet::InterpolationMode;
+use rsqs::filters::FilterChain;
+use rsqs::save::DEFAULT_NAME_TEMPLATE;
+use serde::{Deserialize, Serialize};
+
+use crate::handles::{DEFAULT_HANDLE_SIZE, DEFAULT_HANDLE_TOLERANCE};
+
+/// 配置文件的内容
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub filters: FilterChain,                 // 截图后按顺序应用的滤镜，运行中仍可用快捷键调整
+    pub reveal_after_save: bool,              // 保存后是否在文件管理器中显示文件，菜单中切换时会写回
+    pub interpolation: Option<Interpolation>, // 截图的插值方式，省略时自动选择
+    pub premultiply_clipboard: bool,          // 复制图片前是否预乘 alpha，见 `copy_image_to_clipboard`
+    pub safe_areas: Vec<SafeArea>,            // 追加在内置列表之后的安全区参考框
+    pub handle_size: f64,                     // 选区控制点的边长（像素），触屏上可以调大
+    pub handle_tolerance: f64,                // 控制点四周额外的可抓取范围（像素）
+    pub save_name: String,                    // "复制并保存"时的文件名模板，见 `expand_name`
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            filters: FilterChain::default(),
+            reveal_after_save: false,
+            interpolation: None,
+            premultiply_clipboard: false,
+            safe_areas: Vec::new(),
+            handle_size: DEFAULT_HANDLE_SIZE,
+            handle_tolerance: DEFAULT_HANDLE_TOLERANCE,
+            save_name: DEFAULT_NAME_TEMPLATE.to_string(),
+        }
+    }
+}
+
+/// 安全区参考框：显示的名称和宽高比
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SafeArea {
+    pub name: String, // 显示在参考框左上角的名称
+    pub w: u32,       // 宽高比中的宽
+    pub h: u32,       // 宽高比中的高
+}
+
+/// 绘制截图时的插值方式
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    Nearest,  // 最近邻：像素清晰，缩放显示时有锯齿
+    Bilinear, // 双线性：缩放显示时平滑，1:1 显示时略模糊
+}
+
+impl From<Interpolation> for InterpolationMode {
+    fn from(mode: Interpolation) -> Self {
+        match mode {
+            Interpolation::Nearest => InterpolationMode::NearestNeighbor,
+            Interpolation::Bilinear => InterpolationMode::Bilinear,
+        }
+    }
+}
+
+/// 配置文件的位置，找不到配置目录时返回 `None`
+pub fn file_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("rsqs").join("config.json"))
+}
+
+/// 读取配置；没有配置文件时返回默认配置，文件格式错误时返回错误
+pub fn load() -> Result<Config> {
+    let Some(path) = file_path() else {
+        return Ok(Config::default());
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(e).with_context(|| format!("无法读取 {}", path.display())),
+    };
+    serde_json::from_str(&text).with_context(|| format!("{} 格式错误", path.display()))
+}
+
+/// 修改配置并写回文件
+///
+/// 先重新读取文件再修改，只改动 `edit` 涉及的字段，不会把运行中用快捷键调整的滤镜写进去。
+pub fn update(edit: impl FnOnce(&mut Config)) -> Result<()> {
+    let path = file_path().ok_or_else(|| anyhow!("找不到配置目录"))?;
+    let mut config = load()?;
+    edit(&mut config);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&config)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn omitted_fields_use_defaults() {
+        let config: Config = serde_json::from_str(r#"{"handle_size": 12.0}"#).unwrap();
+        assert_eq!(config.handle_size, 12.0);
+        assert_eq!(config.handle_tolerance, DEFAULT_HANDLE_TOLERANCE);
+        assert!(config.safe_areas.is_empty() && !config.premultiply_clipboard);
+        assert_eq!(config.save_name, DEFAULT_NAME_TEMPLATE);
+    }
+}
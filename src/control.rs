@@ -0,0 +1,225 @@
+// src/control.rs
+//
+// 本地控制接口。需要开启 `control` 特性，通过 `rsqs-control` 程序启动：
+//
+//     cargo run --release --features control --bin rsqs-control -- [地址]
+//
+// 只通过本机的进程间通道通信，不监听任何网络端口：
+//
+// - Unix：Unix 域套接字，默认为运行时目录（没有时为缓存目录）下的 `rsqs/control.sock`。
+//   所在目录创建为 0700，套接字文件为 0600，只有当前用户可以连接。
+// - Windows：命名管道，默认为 `\\.\pipe\rsqs-control-<用户名>`，拒绝来自网络的客户端。
+//   同一时间只有一个管道实例，已被其他程序占用时启动失败，而不是与它共用名字。
+//
+// # 协议
+// 每条请求是一行 UTF-8 文本（以 `\n` 结尾），同一连接上可以连续发送多条请求。
+//
+// - `CAPTURE`：截取整个主显示器
+// - `CAPTURE <x> <y> <w> <h>`：截取主显示器上的指定区域（像素）
+// - `CAPTURE WINDOW`：截取当前获得焦点的窗口
+// - `SCAN CLIPBOARD`：识别剪贴板图片中的二维码
+//
+// 每条请求对应一个响应：
+//
+// - 成功：`OK <类型> <长度>\n`，后面紧跟 `<长度>` 个字节的内容。
+//   `<类型>` 为 `png`（PNG 图像）或 `text`（UTF-8 文本）。
+// - 失败：`ERR <错误信息>\n`，错误信息不含换行。
+
+use std::io::{BufRead, BufReader, Cursor, Write};
+
+use anyhow::{Result, anyhow, bail};
+use arboard::Clipboard;
+use image::{DynamicImage, ImageBuffer, ImageOutputFormat};
+
+use crate::capture::{capture_active_window, capture_primary_monitor};
+use crate::crop::crop_exact;
+use crate::qrcode::scan_qr_code;
+
+/// 请求处理结果
+enum Reply {
+    Png(Vec<u8>),
+    Text(String),
+}
+
+/// 默认的套接字路径，找不到运行时目录和缓存目录时返回 `None`
+#[cfg(unix)]
+pub fn default_address() -> Option<String> {
+    let dir = dirs::runtime_dir().or_else(dirs::cache_dir)?;
+    Some(dir.join("rsqs").join("control.sock").to_string_lossy().into_owned())
+}
+
+/// 默认的管道名，按用户区分
+#[cfg(windows)]
+pub fn default_address() -> Option<String> {
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    Some(format!(r"\\.\pipe\rsqs-control-{}", user))
+}
+
+/// 在 Unix 域套接字 `address` 上监听，依次处理每个连接，直到出错
+#[cfg(unix)]
+pub fn serve(address: &str) -> Result<()> {
+    use std::fs;
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+
+    let path = Path::new(address);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty() && !dir.exists()) {
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    }
+    // 上次异常退出时留下的套接字文件：连不上说明没有程序在监听，可以删除
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            bail!("{} 上已有程序在监听", address);
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let reader = BufReader::new(stream.try_clone()?);
+        if let Err(e) = handle_connection(reader, stream) {
+            eprintln!("连接处理失败: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// 在命名管道 `address` 上监听，依次处理每个连接，直到出错
+#[cfg(windows)]
+pub fn serve(address: &str) -> Result<()> {
+    let pipe = pipe::create(address)?;
+    loop {
+        pipe::connect(&pipe)?;
+        let result = pipe.try_clone().map_err(anyhow::Error::from).and_then(|reader| {
+            handle_connection(BufReader::new(reader), &pipe)
+        });
+        if let Err(e) = result {
+            eprintln!("连接处理失败: {}", e);
+        }
+        // 等客户端读完响应再断开，否则未读的数据会被丢弃
+        pipe.sync_all().ok();
+        pipe::disconnect(&pipe);
+    }
+}
+
+/// 命名管道的系统调用
+#[cfg(windows)]
+mod pipe {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::{AsRawHandle, FromRawHandle};
+
+    use windows_sys::Win32::Foundation::{ERROR_PIPE_CONNECTED, GetLastError, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX};
+    use windows_sys::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_WAIT,
+    };
+
+    /// 管道的读写缓冲区大小
+    const BUFFER_SIZE: u32 = 64 * 1024;
+
+    /// 创建唯一的管道实例；同名管道已经存在时失败
+    pub fn create(name: &str) -> io::Result<File> {
+        let wide: Vec<u16> = name.encode_utf16().chain(Some(0)).collect();
+        let handle = unsafe {
+            CreateNamedPipeW(
+                wide.as_ptr(),
+                PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                1,
+                BUFFER_SIZE,
+                BUFFER_SIZE,
+                0,
+                std::ptr::null(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { File::from_raw_handle(handle) })
+    }
+
+    /// 等待客户端连接
+    pub fn connect(pipe: &File) -> io::Result<()> {
+        let ok = unsafe { ConnectNamedPipe(pipe.as_raw_handle(), std::ptr::null_mut()) };
+        // 客户端在调用前已经连上时返回 ERROR_PIPE_CONNECTED，同样算成功
+        if ok == 0 && unsafe { GetLastError() } != ERROR_PIPE_CONNECTED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// 断开当前客户端，管道实例可以接受下一个连接
+    pub fn disconnect(pipe: &File) {
+        unsafe { DisconnectNamedPipe(pipe.as_raw_handle()) };
+    }
+}
+
+/// 处理一个连接上的所有请求
+fn handle_connection(reader: impl BufRead, mut writer: impl Write) -> Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match handle_request(&line) {
+            Ok(Reply::Png(bytes)) => {
+                writeln!(writer, "OK png {}", bytes.len())?;
+                writer.write_all(&bytes)?;
+            }
+            Ok(Reply::Text(text)) => {
+                writeln!(writer, "OK text {}", text.len())?;
+                writer.write_all(text.as_bytes())?;
+            }
+            Err(e) => writeln!(writer, "ERR {}", e.to_string().replace('\n', " "))?,
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// 解析并执行一条请求
+fn handle_request(line: &str) -> Result<Reply> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["CAPTURE"] => encode_png(&capture_primary_monitor()?).map(Reply::Png),
+        ["CAPTURE", "WINDOW"] => encode_png(&capture_active_window()?).map(Reply::Png),
+        ["CAPTURE", x, y, w, h] => {
+            let region = (x.parse()?, y.parse()?, w.parse()?, h.parse()?);
+            encode_png(&crop_exact(&capture_primary_monitor()?, region)?).map(Reply::Png)
+        }
+        ["SCAN", "CLIPBOARD"] => {
+            let data = Clipboard::new()?.get_image()?;
+            let image = ImageBuffer::from_raw(data.width as u32, data.height as u32, data.bytes.into_owned())
+                .ok_or_else(|| anyhow!("剪贴板图片格式错误"))?;
+            match scan_qr_code(image)? {
+                Some(text) => Ok(Reply::Text(text)),
+                None => bail!("未扫描到二维码"),
+            }
+        }
+        _ => bail!("未知命令: {}", line.trim()),
+    }
+}
+
+/// 把图像编码为 PNG 字节
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_commands_get_one_error_line_each() {
+        let mut out = Vec::new();
+        handle_connection(Cursor::new("HELLO\n\nGET / HTTP/1.1\n"), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "ERR 未知命令: HELLO\nERR 未知命令: GET / HTTP/1.1\n");
+    }
+}
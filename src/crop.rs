@@ -0,0 +1,124 @@
+// src/crop.rs
+
+use anyhow::{Result, bail};
+use druid::Rect;
+use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba};
+
+/// 把逻辑坐标的选区换算成截图内的像素区域 `(x, y, w, h)`
+///
+/// `scale` 为显示器缩放比例。截图是物理像素，而 Druid 的鼠标坐标是逻辑坐标，
+/// 例如 200% 缩放时逻辑坐标 (10, 10)-(20, 20) 对应像素 `(20, 20, 20, 20)`；
+/// 漏乘 `scale` 会裁出左上方一块只有四分之一大小的区域，修改时请保留这一步。
+///
+/// 超出截图边缘的部分会被裁掉；只要选区与截图有重叠，结果至少为 1x1，
+/// 完全不重叠时返回 `None`。
+pub fn pixel_region(rect: Rect, scale: f64, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+    let rect = rect.scale_from_origin(scale);
+    let visible = rect.intersect(Rect::new(0.0, 0.0, width as f64, height as f64));
+    if visible.width() <= 0.0 || visible.height() <= 0.0 {
+        return None;
+    }
+    // 把 [lo, hi) 四舍五入到像素，并保证落在 [0, max] 内且长度不为零
+    let span = |lo: f64, hi: f64, max: u32| {
+        let start = (lo.round() as u32).min(max - 1);
+        let end = (hi.round() as u32).clamp(start + 1, max);
+        (start, end - start)
+    };
+    let (x, w) = span(visible.x0, visible.x1, width);
+    let (y, h) = span(visible.y0, visible.y1, height);
+    Some((x, y, w, h))
+}
+
+/// 裁剪图像中 `rect`（像素坐标）对应的区域，结果为 RGBA8。
+///
+/// 像素的取舍与 [`pixel_region`] 相同：超出图像的部分被裁掉，
+/// 与图像完全不重叠时返回 0x0 的空图像。
+pub fn crop(image: &DynamicImage, rect: Rect) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let (w, h) = image.dimensions();
+    match pixel_region(rect, 1.0, w, h) {
+        Some((x, y, cw, ch)) => image.crop_imm(x, y, cw, ch).to_rgba8(),
+        None => ImageBuffer::new(0, 0),
+    }
+}
+
+/// 裁剪图像中 `(x, y, w, h)` 像素区域，区域必须完全落在图像内且面积不为零。
+///
+/// 与 [`crop`] 不同，超出范围时报错而不是裁掉多出的部分，适合由用户直接给出的坐标。
+pub fn crop_exact(image: &DynamicImage, (x, y, w, h): (u32, u32, u32, u32)) -> Result<DynamicImage> {
+    let (width, height) = image.dimensions();
+    let in_bounds = w > 0
+        && h > 0
+        && x.checked_add(w).is_some_and(|r| r <= width)
+        && y.checked_add(h).is_some_and(|b| b <= height);
+    if !in_bounds {
+        bail!("区域 ({}, {}) {}x{} 超出图像范围 ({}x{})", x, y, w, h, width, height);
+    }
+    Ok(image.crop_imm(x, y, w, h))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 4x4 的测试图像，像素 (x, y) 的颜色为 `[x, y, 0, 255]`，方便检查裁出的位置
+    fn image() -> DynamicImage {
+        DynamicImage::ImageRgba8(ImageBuffer::from_fn(4, 4, |x, y| Rgba([x as u8, y as u8, 0, 255])))
+    }
+
+    /// 裁剪结果的尺寸和左上角像素
+    fn crop_summary(rect: Rect) -> ((u32, u32), Rgba<u8>) {
+        let cropped = crop(&image(), rect);
+        (cropped.dimensions(), *cropped.get_pixel(0, 0))
+    }
+
+    #[test]
+    fn overhanging_left_edge() {
+        assert_eq!(pixel_region(Rect::new(-2.0, 1.0, 2.0, 3.0), 1.0, 4, 4), Some((0, 1, 2, 2)));
+        assert_eq!(crop_summary(Rect::new(-2.0, 1.0, 2.0, 3.0)), ((2, 2), Rgba([0, 1, 0, 255])));
+    }
+
+    #[test]
+    fn overhanging_top_edge() {
+        assert_eq!(pixel_region(Rect::new(1.0, -3.0, 3.0, 2.0), 1.0, 4, 4), Some((1, 0, 2, 2)));
+        assert_eq!(crop_summary(Rect::new(1.0, -3.0, 3.0, 2.0)), ((2, 2), Rgba([1, 0, 0, 255])));
+    }
+
+    #[test]
+    fn overhanging_right_edge() {
+        assert_eq!(pixel_region(Rect::new(3.0, 1.0, 9.0, 2.0), 1.0, 4, 4), Some((3, 1, 1, 1)));
+        assert_eq!(crop_summary(Rect::new(3.0, 1.0, 9.0, 2.0)), ((1, 1), Rgba([3, 1, 0, 255])));
+    }
+
+    #[test]
+    fn overhanging_bottom_edge() {
+        assert_eq!(pixel_region(Rect::new(0.0, 2.0, 4.0, 6.5), 1.0, 4, 4), Some((0, 2, 4, 2)));
+        assert_eq!(crop_summary(Rect::new(0.0, 2.0, 4.0, 6.5)), ((4, 2), Rgba([0, 2, 0, 255])));
+    }
+
+    #[test]
+    fn scales_logical_coordinates() {
+        // 200% 缩放：逻辑坐标 (1, 0.5)-(2, 1.5) 对应像素 (2, 1)-(4, 3)
+        assert_eq!(pixel_region(Rect::new(1.0, 0.5, 2.0, 1.5), 2.0, 4, 4), Some((2, 1, 2, 2)));
+        // 超出右下角的部分同样被裁掉
+        assert_eq!(pixel_region(Rect::new(1.0, 1.0, 3.0, 3.0), 2.0, 4, 4), Some((2, 2, 2, 2)));
+    }
+
+    #[test]
+    fn visible_selection_never_crops_to_nothing() {
+        // 与图像只重叠不到半个像素，四舍五入后仍至少保留 1x1
+        assert_eq!(pixel_region(Rect::new(3.8, 3.8, 5.0, 5.0), 1.0, 4, 4), Some((3, 3, 1, 1)));
+        assert_eq!(pixel_region(Rect::new(1.2, 1.2, 1.4, 1.4), 1.0, 4, 4), Some((1, 1, 1, 1)));
+        // 完全不重叠时没有可裁剪的区域
+        assert_eq!(pixel_region(Rect::new(5.0, 0.0, 8.0, 4.0), 1.0, 4, 4), None);
+        assert_eq!(crop(&image(), Rect::new(-4.0, -4.0, 0.0, 0.0)).dimensions(), (0, 0));
+    }
+
+    #[test]
+    fn exact_crop_rejects_regions_outside_the_image() {
+        let cropped = crop_exact(&image(), (1, 2, 3, 2)).unwrap();
+        assert_eq!((cropped.dimensions(), cropped.get_pixel(0, 0)), ((3, 2), Rgba([1, 2, 0, 255])));
+        assert!(crop_exact(&image(), (2, 0, 3, 1)).is_err());
+        assert!(crop_exact(&image(), (0, 0, 0, 4)).is_err());
+        assert!(crop_exact(&image(), (u32::MAX, 0, 2, 2)).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+// src/ffi.rs
+//
+// C 语言接口。需要开启 `ffi` 特性，并以 cdylib 形式构建：
+//
+//     cargo rustc --release --lib --features ffi --crate-type cdylib
+//
+// # ABI 约定
+// - 所有图像均为 RGBA8、行优先、无行填充，长度恒为 `width * height * 4`。
+// - 返回值：`RSQS_OK`(0) 成功；`RSQS_NOT_FOUND`(1) 未识别到二维码；
+//   `RSQS_INVALID_ARGUMENT`(-1) 参数非法；`RSQS_ERROR`(-2) 内部错误。
+// - 只有返回 `RSQS_OK` 时才会写入输出参数。
+//
+// # 内存所有权
+// - 由本库通过 `out_buf` / `out_text` 返回的缓冲区归调用方所有，
+//   必须且只能用 `rsqs_free(ptr, len)` 释放一次，`len` 为同时返回的长度。
+// - 调用方传入的缓冲区只在调用期间被读取，本库不会保留或释放它们。
+// - 返回的文本是 UTF-8 字节，不以 NUL 结尾。
+
+use std::slice;
+
+use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba};
+
+use crate::capture::capture_primary_monitor;
+use crate::qrcode::scan_qr_code;
+
+pub const RSQS_OK: i32 = 0;
+pub const RSQS_NOT_FOUND: i32 = 1;
+pub const RSQS_INVALID_ARGUMENT: i32 = -1;
+pub const RSQS_ERROR: i32 = -2;
+
+/// 把字节交给调用方，写入输出参数
+///
+/// # Safety
+/// `out_buf` 与 `out_len` 必须非空且可写。
+unsafe fn hand_over(bytes: Vec<u8>, out_buf: *mut *mut u8, out_len: *mut usize) {
+    let boxed = bytes.into_boxed_slice();
+    let len = boxed.len();
+    let ptr = Box::into_raw(boxed) as *mut u8;
+    unsafe {
+        *out_buf = ptr;
+        *out_len = len;
+    }
+}
+
+/// 从调用方传入的缓冲区构造 RGBA 图像（会复制一份数据）
+///
+/// # Safety
+/// `buf` 必须指向至少 `len` 个可读字节。
+unsafe fn borrow_image(
+    buf: *const u8,
+    len: usize,
+    width: u32,
+    height: u32,
+) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    if buf.is_null() || (width as usize) * (height as usize) * 4 != len {
+        return None;
+    }
+    let bytes = unsafe { slice::from_raw_parts(buf, len) };
+    ImageBuffer::from_raw(width, height, bytes.to_vec())
+}
+
+/// 截取主显示器。
+///
+/// # Safety
+/// 所有输出指针必须非空且可写。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsqs_capture_primary_monitor(
+    out_buf: *mut *mut u8,
+    out_len: *mut usize,
+    out_width: *mut u32,
+    out_height: *mut u32,
+) -> i32 {
+    if out_buf.is_null() || out_len.is_null() || out_width.is_null() || out_height.is_null() {
+        return RSQS_INVALID_ARGUMENT;
+    }
+    match capture_primary_monitor() {
+        Ok(img) => {
+            let (w, h) = img.dimensions();
+            unsafe {
+                hand_over(img.to_rgba8().into_raw(), out_buf, out_len);
+                *out_width = w;
+                *out_height = h;
+            }
+            RSQS_OK
+        }
+        Err(_) => RSQS_ERROR,
+    }
+}
+
+/// 从图像中裁剪 `(x, y, w, h)` 区域。区域必须完全落在图像内且面积不为零。
+///
+/// # Safety
+/// `buf` 必须指向至少 `len` 个可读字节；输出指针必须非空且可写。
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn rsqs_crop(
+    buf: *const u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+    out_buf: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32 {
+    if out_buf.is_null() || out_len.is_null() || w == 0 || h == 0 {
+        return RSQS_INVALID_ARGUMENT;
+    }
+    let in_bounds = x.checked_add(w).is_some_and(|r| r <= width)
+        && y.checked_add(h).is_some_and(|b| b <= height);
+    if !in_bounds {
+        return RSQS_INVALID_ARGUMENT;
+    }
+    let Some(img) = (unsafe { borrow_image(buf, len, width, height) }) else {
+        return RSQS_INVALID_ARGUMENT;
+    };
+    let cropped = DynamicImage::ImageRgba8(img).crop_imm(x, y, w, h).to_rgba8();
+    unsafe { hand_over(cropped.into_raw(), out_buf, out_len) };
+    RSQS_OK
+}
+
+/// 扫描图像中的二维码，成功时通过 `out_text` 返回 UTF-8 文本。
+///
+/// # Safety
+/// `buf` 必须指向至少 `len` 个可读字节；输出指针必须非空且可写。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsqs_scan_qr_code(
+    buf: *const u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    out_text: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32 {
+    if out_text.is_null() || out_len.is_null() {
+        return RSQS_INVALID_ARGUMENT;
+    }
+    let Some(img) = (unsafe { borrow_image(buf, len, width, height) }) else {
+        return RSQS_INVALID_ARGUMENT;
+    };
+    match scan_qr_code(img) {
+        Ok(Some(text)) => {
+            unsafe { hand_over(text.into_bytes(), out_text, out_len) };
+            RSQS_OK
+        }
+        Ok(None) => RSQS_NOT_FOUND,
+        Err(_) => RSQS_ERROR,
+    }
+}
+
+/// 释放由本库返回的缓冲区。传入空指针时什么也不做。
+///
+/// # Safety
+/// `ptr` 与 `len` 必须是本库某次调用返回的同一对值，且此前未被释放。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsqs_free(ptr: *mut u8, len: usize) {
+    if ptr.is_null() {
+        return;
+    }
+    drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)) });
+}
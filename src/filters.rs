@@ -0,0 +1,93 @@
+// src/filters.rs
+
+use std::mem::discriminant;
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/// 单个图像滤镜
+///
+/// 在配置文件中写作 `{"name": "brightness", "value": 10}`，
+/// 没有参数的写作 `{"name": "grayscale"}`，
+/// 锐化写作 `{"name": "sharpen", "value": {"sigma": 1.0, "threshold": 2}}`。
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "name", content = "value", rename_all = "snake_case")]
+pub enum Filter {
+    /// 亮度调整，正数变亮、负数变暗
+    Brightness(i32),
+    /// 对比度调整（百分比），正数增强、负数减弱
+    Contrast(f32),
+    /// 灰度
+    Grayscale,
+    /// 锐化（反锐化掩模）
+    Sharpen { sigma: f32, threshold: i32 },
+}
+
+impl Filter {
+    /// 对图像应用该滤镜，返回新图像
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        match *self {
+            Filter::Brightness(value) => image.brighten(value),
+            Filter::Contrast(value) => image.adjust_contrast(value),
+            Filter::Grayscale => image.grayscale(),
+            Filter::Sharpen { sigma, threshold } => image.unsharpen(sigma, threshold),
+        }
+    }
+}
+
+/// 按顺序依次应用的滤镜链，在配置文件中是一个滤镜数组
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FilterChain {
+    pub filters: Vec<Filter>,
+}
+
+impl FilterChain {
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// 依次应用所有滤镜，结果统一为 RGBA8
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        let filtered = self
+            .filters
+            .iter()
+            .fold(image.clone(), |img, filter| filter.apply(&img));
+        DynamicImage::ImageRgba8(filtered.to_rgba8())
+    }
+
+    /// 链中已有同类滤镜则移除，否则追加到末尾
+    pub fn toggle(&mut self, filter: Filter) {
+        let len = self.filters.len();
+        self.filters.retain(|f| discriminant(f) != discriminant(&filter));
+        if self.filters.len() == len {
+            self.filters.push(filter);
+        }
+    }
+
+    /// 累加亮度；调整到 0 时移除该滤镜
+    pub fn adjust_brightness(&mut self, delta: i32) {
+        let existing = self.filters.iter_mut().find(|f| matches!(f, Filter::Brightness(_)));
+        if let Some(Filter::Brightness(value)) = existing {
+            *value += delta;
+        } else {
+            self.filters.push(Filter::Brightness(delta));
+        }
+        self.filters.retain(|f| *f != Filter::Brightness(0));
+    }
+
+    /// 累加对比度；调整到 0 时移除该滤镜
+    pub fn adjust_contrast(&mut self, delta: f32) {
+        let existing = self.filters.iter_mut().find(|f| matches!(f, Filter::Contrast(_)));
+        if let Some(Filter::Contrast(value)) = existing {
+            *value += delta;
+        } else {
+            self.filters.push(Filter::Contrast(delta));
+        }
+        self.filters.retain(|f| !matches!(f, Filter::Contrast(v) if v.abs() < f32::EPSILON));
+    }
+
+    pub fn clear(&mut self) {
+        self.filters.clear();
+    }
+}
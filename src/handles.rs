@@ -0,0 +1,180 @@
+// src/handles.rs
+
+use druid::{Cursor, Point, Rect, Size, Vec2};
+
+/// 选区控制点的默认边长（像素），可在配置文件中修改
+pub const DEFAULT_HANDLE_SIZE: f64 = 8.0;
+/// 控制点四周额外的可抓取范围的默认值（像素），可在配置文件中修改
+pub const DEFAULT_HANDLE_TOLERANCE: f64 = 4.0;
+
+/// 调整选区时抓住的位置
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Handle {
+    TopLeft,
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+    /// 选区内部：整体移动
+    Move,
+}
+
+impl Handle {
+    /// 八个控制点（四角 + 四边中点）
+    pub const ALL: [Handle; 8] = [
+        Handle::TopLeft,
+        Handle::Top,
+        Handle::TopRight,
+        Handle::Right,
+        Handle::BottomRight,
+        Handle::Bottom,
+        Handle::BottomLeft,
+        Handle::Left,
+    ];
+
+    /// 四角的控制点；锁定宽高比时只能拖动这些
+    pub const CORNERS: [Handle; 4] = [Handle::TopLeft, Handle::TopRight, Handle::BottomRight, Handle::BottomLeft];
+
+    /// 可以拖动的控制点：锁定宽高比时四边中点会破坏比例，只保留四角
+    pub fn available(locked: bool) -> &'static [Handle] {
+        if locked { &Handle::CORNERS } else { &Handle::ALL }
+    }
+
+    /// 控制点在选区上的中心位置
+    fn anchor(self, rect: Rect) -> Point {
+        let c = rect.center();
+        match self {
+            Handle::TopLeft => Point::new(rect.x0, rect.y0),
+            Handle::Top => Point::new(c.x, rect.y0),
+            Handle::TopRight => Point::new(rect.x1, rect.y0),
+            Handle::Right => Point::new(rect.x1, c.y),
+            Handle::BottomRight => Point::new(rect.x1, rect.y1),
+            Handle::Bottom => Point::new(c.x, rect.y1),
+            Handle::BottomLeft => Point::new(rect.x0, rect.y1),
+            Handle::Left => Point::new(rect.x0, c.y),
+            Handle::Move => c,
+        }
+    }
+
+    /// 控制点绘制出的边长为 `size` 的小方块
+    pub fn square(self, rect: Rect, size: f64) -> Rect {
+        Rect::from_center_size(self.anchor(rect), (size, size))
+    }
+
+    /// 鼠标悬停在该位置时显示的光标
+    pub fn cursor(self) -> Cursor {
+        match self {
+            Handle::Left | Handle::Right => Cursor::ResizeLeftRight,
+            Handle::Top | Handle::Bottom => Cursor::ResizeUpDown,
+            Handle::Move => Cursor::Pointer,
+            _ => Cursor::Crosshair,
+        }
+    }
+
+    /// 拖动该位置 `delta` 后的新选区，结果限制在 `bounds` 内
+    pub fn drag(self, rect: Rect, delta: Vec2, bounds: Size) -> Rect {
+        if self == Handle::Move {
+            // 整体移动时保持大小，只限制位置
+            let x = (rect.x0 + delta.x).clamp(0.0, (bounds.width - rect.width()).max(0.0));
+            let y = (rect.y0 + delta.y).clamp(0.0, (bounds.height - rect.height()).max(0.0));
+            return rect.with_origin((x, y));
+        }
+
+        let mut r = rect;
+        if matches!(self, Handle::TopLeft | Handle::Left | Handle::BottomLeft) {
+            r.x0 += delta.x;
+        }
+        if matches!(self, Handle::TopRight | Handle::Right | Handle::BottomRight) {
+            r.x1 += delta.x;
+        }
+        if matches!(self, Handle::TopLeft | Handle::Top | Handle::TopRight) {
+            r.y0 += delta.y;
+        }
+        if matches!(self, Handle::BottomLeft | Handle::Bottom | Handle::BottomRight) {
+            r.y1 += delta.y;
+        }
+        // 拖过对边时自动翻转
+        r.abs().intersect(bounds.to_rect())
+    }
+
+    /// 锁定宽高比 `ratio`（宽 / 高）时拖动该位置 `delta` 后的新选区，结果限制在 `bounds` 内
+    ///
+    /// 拖动四角时以对角为锚点，移动较多的方向决定大小，另一边按比例跟随；
+    /// 四边中点不能在锁定时拖动，返回原选区。整体移动与 [`Handle::drag`] 相同。
+    pub fn drag_locked(self, rect: Rect, delta: Vec2, bounds: Size, ratio: f64) -> Rect {
+        let (anchor, corner) = match self {
+            Handle::TopLeft => ((rect.x1, rect.y1), (rect.x0, rect.y0)),
+            Handle::TopRight => ((rect.x0, rect.y1), (rect.x1, rect.y0)),
+            Handle::BottomRight => ((rect.x0, rect.y0), (rect.x1, rect.y1)),
+            Handle::BottomLeft => ((rect.x1, rect.y0), (rect.x0, rect.y1)),
+            Handle::Move => return self.drag(rect, delta, bounds),
+            _ => return rect,
+        };
+        let anchor = Point::from(anchor);
+        let dir = Point::from(corner) + delta - anchor;
+        let size = if delta.x.abs() >= delta.y.abs() * ratio {
+            Size::new(dir.x.abs(), dir.x.abs() / ratio)
+        } else {
+            Size::new(dir.y.abs() * ratio, dir.y.abs())
+        };
+        fit_from_anchor(anchor, size, dir, bounds)
+    }
+}
+
+/// 以 `anchor` 为一角、朝 `dir` 所指的方向展开大小为 `size` 的矩形
+///
+/// 超出 `bounds` 时宽高按同一比例缩小，结果保持 `size` 的宽高比。
+pub fn fit_from_anchor(anchor: Point, size: Size, dir: Vec2, bounds: Size) -> Rect {
+    // 朝展开方向还能容纳的宽和高
+    let room_w = if dir.x < 0.0 { anchor.x } else { bounds.width - anchor.x };
+    let room_h = if dir.y < 0.0 { anchor.y } else { bounds.height - anchor.y };
+    let fit = |room: f64, len: f64| if len > room { room.max(0.0) / len } else { 1.0 };
+    let k = fit(room_w, size.width).min(fit(room_h, size.height));
+    let end = anchor + Vec2::new((size.width * k).copysign(dir.x), (size.height * k).copysign(dir.y));
+    Rect::from_points(anchor, end).abs()
+}
+
+/// 判断鼠标位置落在选区的哪个控制点上；控制点优先于选区内部
+///
+/// `handles` 为可以拖动的控制点（见 [`Handle::available`]），`size` 为控制点的边长，
+/// `tolerance` 为四周额外的可抓取范围。
+pub fn hit_test(rect: Rect, pos: Point, handles: &[Handle], size: f64, tolerance: f64) -> Option<Handle> {
+    handles
+        .iter()
+        .copied()
+        .find(|h| h.square(rect, size).inflate(tolerance, tolerance).contains(pos))
+        .or_else(|| rect.contains(pos).then_some(Handle::Move))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: Size = Size::new(400.0, 300.0);
+
+    #[test]
+    fn locked_corner_drag_keeps_ratio() {
+        let rect = Rect::new(100.0, 100.0, 260.0, 180.0); // 2:1
+        // 主要向右拖：宽度决定大小，高度跟随
+        let r = Handle::BottomRight.drag_locked(rect, Vec2::new(32.0, 5.0), BOUNDS, 2.0);
+        assert_eq!(r, Rect::new(100.0, 100.0, 292.0, 196.0));
+        // 向内拖左上角同样可以缩小
+        let r = Handle::TopLeft.drag_locked(rect, Vec2::new(0.0, 18.0), BOUNDS, 2.0);
+        assert_eq!(r, Rect::new(136.0, 118.0, 260.0, 180.0));
+    }
+
+    #[test]
+    fn locked_drag_stays_inside_bounds_and_ignores_edges() {
+        let rect = Rect::new(200.0, 100.0, 300.0, 200.0);
+        let r = Handle::BottomRight.drag_locked(rect, Vec2::new(300.0, 0.0), BOUNDS, 1.0);
+        assert_eq!(r, Rect::new(200.0, 100.0, 400.0, 300.0));
+        assert_eq!(Handle::Right.drag_locked(rect, Vec2::new(20.0, 0.0), BOUNDS, 1.0), rect);
+        // 右边中点：锁定时只能整体移动
+        let pos = Point::new(298.0, 150.0);
+        assert_eq!(hit_test(rect, pos, Handle::available(false), 8.0, 4.0), Some(Handle::Right));
+        assert_eq!(hit_test(rect, pos, Handle::available(true), 8.0, 4.0), Some(Handle::Move));
+    }
+}
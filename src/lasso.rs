@@ -0,0 +1,153 @@
+// src/lasso.rs
+
+use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba};
+
+/// 判断点是否在多边形内（奇偶规则，适用于凹多边形）。
+///
+/// `polygon` 按顺序给出各顶点，首尾自动相连。
+pub fn point_in_polygon(polygon: &[(f64, f64)], (x, y): (f64, f64)) -> bool {
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+    for (i, &(xi, yi)) in polygon.iter().enumerate() {
+        let (xj, yj) = polygon[j];
+        // 从该点向右的射线与边 (i, j) 相交时翻转一次
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// 把多边形外的像素设为完全透明。
+///
+/// `polygon` 使用图像内的像素坐标，按像素中心判断是否在多边形内，结果与 [`point_in_polygon`] 一致。
+/// 套索每移动 1 像素就有一个顶点，逐像素判断会随顶点数成倍变慢；
+/// 这里按扫描线每行只求一次与各边的交点，再整段填充。
+pub fn mask_outside_polygon(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, polygon: &[(f64, f64)]) {
+    let (w, h) = image.dimensions();
+    // 交点横坐标换算为第一个像素中心不小于它的列
+    let column = |cross: f64| (cross - 0.5).ceil().clamp(0.0, w as f64) as u32;
+    let mut crossings = Vec::new();
+    for y in 0..h {
+        let yc = y as f64 + 0.5;
+        crossings.clear();
+        let mut j = polygon.len().wrapping_sub(1);
+        for (i, &(xi, yi)) in polygon.iter().enumerate() {
+            let (xj, yj) = polygon[j];
+            if (yi > yc) != (yj > yc) {
+                crossings.push((xj - xi) * (yc - yi) / (yj - yi) + xi);
+            }
+            j = i;
+        }
+        crossings.sort_by(f64::total_cmp);
+
+        // 奇偶规则：第 2k 个与第 2k+1 个交点之间在多边形内，其余设为透明
+        let mut x = 0;
+        for span in crossings.chunks_exact(2) {
+            clear_row(image, y, x..column(span[0]));
+            x = x.max(column(span[1]));
+        }
+        clear_row(image, y, x..w);
+    }
+}
+
+/// 把第 `y` 行中 `columns` 范围内的像素设为完全透明
+fn clear_row(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, y: u32, columns: std::ops::Range<u32>) {
+    for x in columns {
+        image.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+    }
+}
+
+/// 按多边形裁剪图像：结果为多边形外接矩形大小的 RGBA 图像，多边形外的像素完全透明。
+///
+/// `polygon` 使用 `image` 内的像素坐标。
+///
+/// # 返回
+/// - `Some(image)`: 裁剪结果。
+/// - `None`: 顶点少于 3 个，或外接矩形与图像不重叠。
+pub fn crop_polygon(image: &DynamicImage, polygon: &[(f64, f64)]) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    if polygon.len() < 3 {
+        return None;
+    }
+    let (w, h) = image.dimensions();
+    let fold = |init: f64, f: fn(f64, f64) -> f64, pick: fn(&(f64, f64)) -> f64| {
+        polygon.iter().map(pick).fold(init, f)
+    };
+    let x0 = fold(f64::INFINITY, f64::min, |p| p.0).floor().max(0.0) as u32;
+    let y0 = fold(f64::INFINITY, f64::min, |p| p.1).floor().max(0.0) as u32;
+    let x1 = (fold(f64::NEG_INFINITY, f64::max, |p| p.0).ceil().max(0.0) as u32).min(w);
+    let y1 = (fold(f64::NEG_INFINITY, f64::max, |p| p.1).ceil().max(0.0) as u32).min(h);
+    if x0 >= x1 || y0 >= y1 {
+        return None;
+    }
+
+    let mut cropped = image.crop_imm(x0, y0, x1 - x0, y1 - y0).to_rgba8();
+    let shifted: Vec<(f64, f64)> = polygon.iter().map(|&(x, y)| (x - x0 as f64, y - y0 as f64)).collect();
+    mask_outside_polygon(&mut cropped, &shifted);
+    Some(cropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 开口朝上的 U 形（凹多边形），中间的缺口在多边形外
+    const U_SHAPE: [(f64, f64); 8] =
+        [(1.0, 1.0), (3.0, 1.0), (3.0, 6.0), (5.0, 6.0), (5.0, 1.0), (7.0, 1.0), (7.0, 8.0), (1.0, 8.0)];
+
+    fn opaque(width: u32, height: u32) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        ImageBuffer::from_pixel(width, height, Rgba([255, 0, 0, 255]))
+    }
+
+    #[test]
+    fn point_in_concave_polygon() {
+        assert!(point_in_polygon(&U_SHAPE, (2.0, 3.0))); // 左臂
+        assert!(point_in_polygon(&U_SHAPE, (6.0, 3.0))); // 右臂
+        assert!(point_in_polygon(&U_SHAPE, (4.0, 7.0))); // 底部
+        assert!(!point_in_polygon(&U_SHAPE, (4.0, 3.0))); // 缺口
+        assert!(!point_in_polygon(&U_SHAPE, (0.5, 3.0)));
+    }
+
+    #[test]
+    fn mask_clears_the_notch_of_a_concave_shape() {
+        let mut image = opaque(8, 9);
+        mask_outside_polygon(&mut image, &U_SHAPE);
+        assert_eq!(image.get_pixel(1, 2).0[3], 255);
+        assert_eq!(image.get_pixel(6, 2).0[3], 255);
+        assert_eq!(image.get_pixel(4, 7).0[3], 255);
+        assert_eq!(image.get_pixel(4, 2).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(0, 2).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(3, 8).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn mask_matches_point_in_polygon() {
+        // 自相交的五角星和边缘落在像素中心之间的凹多边形
+        let star: Vec<(f64, f64)> = (0..5)
+            .map(|i| {
+                let angle = i as f64 * 4.0 * std::f64::consts::PI / 5.0;
+                (16.0 + 14.0 * angle.sin(), 16.0 - 14.0 * angle.cos())
+            })
+            .collect();
+        let jagged = [(0.3, 0.3), (30.7, 2.2), (12.4, 12.6), (31.5, 29.1), (2.9, 31.8), (-4.0, 15.5)];
+        for polygon in [&star[..], &jagged[..]] {
+            let mut image = opaque(32, 32);
+            mask_outside_polygon(&mut image, polygon);
+            for (x, y, pixel) in image.enumerate_pixels() {
+                let inside = point_in_polygon(polygon, (x as f64 + 0.5, y as f64 + 0.5));
+                assert_eq!(pixel.0[3] == 255, inside, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn crop_polygon_is_transparent_outside() {
+        let image = DynamicImage::ImageRgba8(opaque(10, 10));
+        let cropped = crop_polygon(&image, &U_SHAPE).unwrap();
+        assert_eq!(cropped.dimensions(), (6, 7));
+        assert_eq!(cropped.get_pixel(3, 1).0, [0, 0, 0, 0]);
+        assert_eq!(cropped.get_pixel(0, 0).0[3], 255);
+        assert!(crop_polygon(&image, &U_SHAPE[..2]).is_none());
+    }
+}
@@ -0,0 +1,50 @@
+// src/last_selection.rs
+//
+// 记住上一次复制/保存时的选区，下次启动时直接预选，按 Enter 或右键即可确认。
+// 保存在配置目录下的 `rsqs/last_selection.json`，单位为截图像素。
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+/// 截图像素坐标下的选区
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 记录文件的位置，找不到配置目录时返回 `None`
+fn file_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("rsqs").join("last_selection.json"))
+}
+
+/// 保存选区，覆盖上一次的记录
+pub fn save(rect: PixelRect) -> Result<()> {
+    let path = file_path().ok_or_else(|| anyhow!("找不到配置目录"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string(&rect)?)?;
+    Ok(())
+}
+
+/// 读取上一次的选区，并裁剪到 `width` x `height` 的截图范围内
+///
+/// 分辨率可能在两次运行之间变化；没有记录、记录损坏或与截图完全不重叠时返回 `None`。
+pub fn load(width: u32, height: u32) -> Option<PixelRect> {
+    let text = fs::read_to_string(file_path()?).ok()?;
+    let rect: PixelRect = serde_json::from_str(&text).ok()?;
+    let right = rect.x.saturating_add(rect.width).min(width);
+    let bottom = rect.y.saturating_add(rect.height).min(height);
+    (rect.x < right && rect.y < bottom).then(|| PixelRect {
+        x: rect.x,
+        y: rect.y,
+        width: right - rect.x,
+        height: bottom - rect.y,
+    })
+}
@@ -0,0 +1,25 @@
+// src/lib.rs
+//
+// 截图与二维码识别的核心逻辑，供 rsqs 可执行文件和（可选的）C 接口共用。
+//
+// 也可以作为库直接使用：截图 → 裁剪 → 识别二维码 / 复制到剪贴板的常用函数在根模块重新导出。
+
+pub mod capture; // 屏幕捕获模块
+pub mod clipboard; // 剪贴板辅助函数
+pub mod crop; // 选区裁剪
+pub mod filters; // 图像滤镜模块
+pub mod lasso; // 套索（多边形）裁剪
+pub mod qrcode; // 二维码识别模块
+pub mod save; // 图片保存模块
+pub mod stitch; // 滚动截图拼接
+
+pub use self::capture::capture_primary_monitor as capture_primary;
+pub use self::clipboard::{copy_image_to_clipboard, copy_text_to_clipboard};
+pub use self::crop::{crop, pixel_region};
+pub use self::qrcode::scan_qr_code;
+
+#[cfg(feature = "ffi")]
+pub mod ffi; // C 语言接口
+
+#[cfg(feature = "control")]
+pub mod control; // 本地控制接口
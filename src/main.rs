@@ -2,156 +2,987 @@
 
 // ----------- 依赖导入 -----------
 use anyhow::Result;
-use arboard::{Clipboard, ImageData}; // 剪贴板操作
+use arboard::Clipboard; // 剪贴板操作
+use druid::kurbo::{BezPath, Shape};
 use druid::menu::MenuEventCtx;
-use druid::piet::PietImage;
+use druid::piet::{InterpolationMode, PietImage, StrokeStyle, Text, TextLayout, TextLayoutBuilder};
 use druid::{
-    AppLauncher, BoxConstraints, Color, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle,
-    LifeCycleCtx, Menu, MenuItem, PaintCtx, Point, Rect, RenderContext, Size, UpdateCtx, Widget,
-    WindowDesc, SysMods,
+    Affine, AppLauncher, BoxConstraints, Color, Cursor, Data, Env, Event, EventCtx, FontFamily, LayoutCtx,
+    LifeCycle, LifeCycleCtx, Menu, MenuItem, PaintCtx, Point, Rect, RenderContext, Size, UpdateCtx,
+    MouseEvent, Selector, Vec2, Widget, WindowDesc,
 };
 use druid::keyboard_types::Key;
 
 
 //  image v0.24.9
 use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba}; // 图像处理
-use rfd::MessageDialog; // 文件/消息对话框
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult}; // 文件/消息对话框
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use xcap::Monitor; // 屏幕捕获
 
-mod qrcode; // 二维码识别模块
-use qrcode::scan_qr_code;
+use rsqs::capture::{active_app_name, capture_active_window, capture_active_window_region, capture_scrolling, capture_target}; // 屏幕捕获模块
+use rsqs::clipboard::{copy_image_to_clipboard, copy_text_to_clipboard}; // 剪贴板辅助函数
+use rsqs::crop::pixel_region; // 选区换算与裁剪
+use rsqs::filters::{Filter, FilterChain}; // 图像滤镜模块
+use rsqs::lasso::mask_outside_polygon; // 套索裁剪
+use rsqs::qrcode::{generate_qr, scan_all_codes}; // 二维码识别/生成模块
+use rsqs::save::{DEFAULT_NAME_TEMPLATE, NameFields, SAVE_FORMATS, check_name_template, default_save_path, save_image}; // 图片保存模块
+
+mod handles; // 选区控制点
+use handles::{Handle, fit_from_anchor, hit_test};
+
+mod reveal; // 在文件管理器中显示文件
+use reveal::reveal_in_file_manager;
+
+mod pin; // 钉在屏幕上的图片窗口
+use pin::pin_window;
+
+mod annotate; // 矩形、箭头、画笔标注
+use annotate::{Annotation, Tool};
+
+mod redact; // 马赛克
+
+mod last_selection; // 记住上一次的选区
+use last_selection::PixelRect;
+
+mod config; // 用户配置文件
+use config::SafeArea;
+
+mod args; // 命令行参数
+use args::parse_args;
 
 // ----------- 应用状态结构体 -----------
 #[derive(Clone, Data)]
 /// 保存截图、选区状态等信息
 struct AppState {
     #[data(same_fn = "PartialEq::eq")]
-    screenshot: Arc<DynamicImage>, // 当前屏幕截图
-    is_selecting: bool,            // 是否正在选择区域
-    start_pos: Point,              // 选择起点
-    current_pos: Point,            // 当前鼠标位置
-    selection_rect: Option<Rect>,  // 已确定的选区
+    screenshot: Arc<DynamicImage>,     // 当前屏幕截图（已应用滤镜）
+    #[data(same_fn = "PartialEq::eq")]
+    original: Arc<DynamicImage>,       // 未经处理的原始截图
+    #[data(same_fn = "PartialEq::eq")]
+    filters: FilterChain,              // 当前滤镜链
+    origin: Point,                     // 截图所在显示器左上角在虚拟桌面中的位置（逻辑坐标）
+    scale: f64,                        // 显示器缩放比例：每个逻辑坐标单位对应的截图像素数
+    is_selecting: bool,                // 是否正在选择区域
+    start_pos: Point,                  // 选择起点
+    current_pos: Point,                // 当前鼠标位置
+    selection_rect: Option<Rect>,      // 已确定的选区
+    locked_ratio: Option<(u32, u32)>,  // 锁定的选区宽高比，None 表示自由
+    reveal_after_save: bool,           // 保存后是否打开所在文件夹
+    premultiply_clipboard: bool,       // 复制图片前是否预乘 alpha（见 `copy_image_to_clipboard`）
+    save_name: String,                 // "复制并保存"时的文件名模板
+    app_name: String,                  // 截图时位于前台的程序名，不知道时为空
+    show_guide: bool,                  // 是否显示安全区参考框
+    guide_index: usize,                // 当前参考框在 safe_areas 中的序号
+    #[data(same_fn = "PartialEq::eq")]
+    safe_areas: Arc<Vec<SafeArea>>,    // 可选的安全区参考框，见 `safe_areas`
+    last_action: Option<MenuAction>,   // 上一次执行的菜单动作
+    picking_color: bool,               // 是否处于取色模式
+    #[data(same_fn = "PartialEq::eq")]
+    annotations: Vec<Annotation>,      // 已画好的标注，复制/保存时画进图像
+    tool: Option<Tool>,                // 当前的标注工具，None 表示不在标注模式
+    mosaic_block: u32,                 // 马赛克块大小（截图像素）
+    lasso_mode: bool,                  // 是否用套索（自由形状）选择
+    #[data(same_fn = "PartialEq::eq")]
+    lasso: Vec<Point>,                 // 套索路径的顶点；为空表示矩形选区
 }
 
 impl AppState {
     /// 获取当前拖动的选区（未最终确定时）
+    ///
+    /// 锁定了宽高比时，以起点为锚点，把较短的一边放大到符合比例；
+    /// 放大后超出截图范围时两边按比例一起缩小，保证裁剪结果仍是锁定的比例。
     fn get_current_selection(&self) -> Rect {
-        Rect::from_points(self.start_pos, self.current_pos).abs()
+        let Some(ratio) = self.ratio() else {
+            return Rect::from_points(self.start_pos, self.current_pos).abs();
+        };
+        let dir = self.current_pos - self.start_pos;
+        let (w, h) = (dir.x.abs(), dir.y.abs());
+        let (w, h) = if w > h * ratio { (w, w / ratio) } else { (h * ratio, h) };
+        fit_from_anchor(self.start_pos, Size::new(w, h), dir, self.bounds())
+    }
+
+    /// 锁定的宽高比（宽 / 高），没有锁定时为 `None`
+    fn ratio(&self) -> Option<f64> {
+        self.locked_ratio.map(|(rw, rh)| rw as f64 / rh as f64)
+    }
+
+    /// 用 `handle` 把已确定的选区 `rect` 拖动 `delta`；锁定了宽高比时保持比例
+    fn drag_selection(&self, handle: Handle, rect: Rect, delta: Vec2) -> Rect {
+        match self.ratio() {
+            Some(ratio) => handle.drag_locked(rect, delta, self.bounds(), ratio),
+            None => handle.drag(rect, delta, self.bounds()),
+        }
+    }
+
+    /// 截图在逻辑坐标下的范围，选区、标注等都限制在其中
+    fn bounds(&self) -> Size {
+        logical_size(&self.screenshot, self.scale)
     }
 
     /// 裁剪选区对应的图像
+    ///
+    /// 选区使用截图的逻辑坐标（截图像素除以 `scale`），乘以 `scale` 就是截图内的像素坐标；
+    /// 虚拟桌面上的位置等于选区坐标加上 `origin`。窗口经过缩放和平移时，
+    /// 选区在窗口中的位置是 `transform * p`（见 `ScreenshotWidget::transform`），与选区坐标无关。
     fn crop_image(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        self.selection_rect.map(|rect| {
-            self.screenshot
-                .crop_imm(
-                    rect.x0.max(0.0) as u32,
-                    rect.y0.max(0.0) as u32,
-                    rect.width() as u32,
-                    rect.height() as u32,
-                )
-                .to_rgba8()
-        })
+        let rect = self.selection_rect?;
+        let (w, h) = self.screenshot.dimensions();
+        let (x, y, cw, ch) = pixel_region(rect, self.scale, w, h)?;
+        let offset = Vec2::new(x as f64, y as f64);
+        let mut img = self.screenshot.crop_imm(x, y, cw, ch).to_rgba8();
+        // 先打马赛克再画线条，线条不会被马赛克盖住
+        for region in self.annotations.iter().filter_map(Annotation::mosaic_region) {
+            redact::apply_pixelation(&mut img, region.scale_from_origin(self.scale) - offset, self.mosaic_block);
+        }
+        annotate::bake(&mut img, &self.annotations, offset, self.scale);
+        // 套索选区：裁剪的是外接矩形，路径外的像素设为透明
+        if self.lasso.len() >= 3 {
+            let polygon: Vec<(f64, f64)> = self
+                .lasso
+                .iter()
+                .map(|p| (p.x * self.scale - offset.x, p.y * self.scale - offset.y))
+                .collect();
+            mask_outside_polygon(&mut img, &polygon);
+        }
+        Some(img)
+    }
+
+    /// `crop_image` 裁剪结果的左上角在虚拟桌面中的位置（逻辑坐标）
+    fn crop_origin(&self) -> Option<Point> {
+        let (w, h) = self.screenshot.dimensions();
+        let (x, y, _, _) = pixel_region(self.selection_rect?, self.scale, w, h)?;
+        Some(self.origin + Vec2::new(x as f64, y as f64) / self.scale)
+    }
+
+    /// 记住当前选区，供下次启动时预选；失败时只打印日志
+    fn remember_selection(&self) {
+        let (w, h) = self.screenshot.dimensions();
+        let Some((x, y, width, height)) = self.selection_rect.and_then(|r| pixel_region(r, self.scale, w, h)) else {
+            return;
+        };
+        if let Err(e) = last_selection::save(PixelRect { x, y, width, height }) {
+            eprintln!("保存选区失败: {}", e);
+        }
+    }
+
+    /// 鼠标位置对应的截图像素坐标，与 `crop_image` 使用同样的换算
+    fn pixel_at(&self, pos: Point) -> Option<(u32, u32)> {
+        let (w, h) = self.screenshot.dimensions();
+        let cell = Rect::from_origin_size(((pos.x * self.scale).floor(), (pos.y * self.scale).floor()), (1.0, 1.0));
+        pixel_region(cell, 1.0, w, h).map(|(x, y, _, _)| (x, y))
+    }
+
+    /// 鼠标位置处像素的颜色，格式为 `#RRGGBB`
+    fn color_hex_at(&self, pos: Point) -> Option<String> {
+        let (x, y) = self.pixel_at(pos)?;
+        let Rgba([r, g, b, _]) = self.screenshot.get_pixel(x, y);
+        Some(format!("#{:02X}{:02X}{:02X}", r, g, b))
+    }
+
+    /// 套索选区的闭合轮廓，顶点不足 3 个时返回 `None`
+    fn lasso_path(&self) -> Option<BezPath> {
+        (self.lasso.len() >= 3).then(|| polyline(&self.lasso, true))
+    }
+
+    /// 在 `pos` 处结束拖动选择；`pos` 会被限制在截图范围内（在窗口外松开时选区停在边缘）
+    ///
+    /// # 返回
+    /// 确定下来的选区；选区太小（通常是单击而非拖动）时清除选区并返回 `None`。
+    fn release_selection(&mut self, pos: Point) -> Option<Rect> {
+        self.is_selecting = false;
+        self.current_pos = clamp_to_size(pos, self.bounds());
+        let sel = self.get_current_selection();
+        // 只有当选区足够大时才认为是有效选择
+        self.selection_rect = (sel.width() > 1.0 && sel.height() > 1.0).then_some(sel);
+        self.selection_rect
+    }
+
+    /// 结束描套索：围出的区域足够大时以外接矩形作为选区，否则清除路径
+    ///
+    /// # 返回
+    /// 确定下来的选区（套索路径的外接矩形）。
+    fn close_lasso(&mut self) -> Option<Rect> {
+        let bounds = self
+            .lasso_path()
+            .map(|path| path.bounding_box())
+            .filter(|b| b.width() > 1.0 && b.height() > 1.0);
+        match bounds {
+            Some(bounds) => self.selection_rect = Some(bounds),
+            None => self.lasso.clear(),
+        }
+        bounds
+    }
+
+    /// 选择标注工具；再次选择同一个工具时退出标注模式
+    fn toggle_tool(&mut self, tool: Tool) {
+        self.tool = if self.tool == Some(tool) { None } else { Some(tool) };
+    }
+
+    /// 修改滤镜链并立即刷新显示用的截图
+    fn edit_filters(&mut self, edit: impl FnOnce(&mut FilterChain)) {
+        edit(&mut self.filters);
+        self.apply_filters();
+    }
+
+    /// 根据当前滤镜链，从原始截图重新生成显示用的截图
+    fn apply_filters(&mut self) {
+        self.screenshot = if self.filters.is_empty() {
+            self.original.clone()
+        } else {
+            Arc::new(self.filters.apply(&self.original))
+        };
     }
     
 }
 
-// ----------- 剪贴板辅助函数 -----------
-/// 将图片复制到剪贴板
-fn copy_image_to_clipboard(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<()> {
-    let mut clipboard = Clipboard::new()?;
-    let image_data = ImageData {
-        width: image.width() as usize,
-        height: image.height() as usize,
-        bytes: image.as_raw().into(),
-    };
-    clipboard.set_image(image_data)?;
-    Ok(())
+// ----------- 截图控件实现 -----------
+/// 生成缓存图像的最大尝试次数
+const MAX_IMAGE_ATTEMPTS: u32 = 3;
+/// 两次轻点被视为双击的最大间隔
+const DOUBLE_TAP_INTERVAL: Duration = Duration::from_millis(400);
+/// 两次轻点被视为双击的最大距离（像素）
+const DOUBLE_TAP_DISTANCE: f64 = 10.0;
+/// 滚轮每一格的缩放倍数
+const ZOOM_STEP: f64 = 1.25;
+/// 缩放范围
+const MIN_ZOOM: f64 = 0.25;
+const MAX_ZOOM: f64 = 16.0;
+/// 把当前选区钉在屏幕上；菜单无法直接创建窗口，由截图控件处理
+const PIN_SELECTION: Selector = Selector::new("rsqs.pin-selection");
+
+/// 截图窗口的外观
+#[derive(Clone, Debug)]
+struct Theme {
+    mask_alpha: u8,        // 选区外遮罩的不透明度
+    border_color: Color,   // 选区边框颜色
+    border_width: f64,     // 选区边框宽度
+    idle_dim_alpha: u8,    // 还没有选区时整个屏幕变暗的不透明度
+    handle_size: f64,      // 控制点的边长（窗口像素）
+    handle_tolerance: f64, // 控制点四周额外的可抓取范围（窗口像素）
 }
 
-/// 将文本复制到剪贴板
-fn copy_text_to_clipboard(text: &str) -> Result<()> {
-    let mut clipboard = Clipboard::new()?;
-    clipboard.set_text(text.to_string())?;
-    Ok(())
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            mask_alpha: 128,
+            border_color: Color::WHITE,
+            border_width: 1.0,
+            idle_dim_alpha: 72,
+            handle_size: handles::DEFAULT_HANDLE_SIZE,
+            handle_tolerance: handles::DEFAULT_HANDLE_TOLERANCE,
+        }
+    }
 }
 
-// ----------- 截图控件实现 -----------
 struct ScreenshotWidget {
-    cached_image: Option<PietImage>, // 缓存的屏幕图像
-    previous_rect: Option<Rect>,     // 上一次选区
+    theme: Theme,                              // 遮罩和边框的外观
+    cached_image: Option<PietImage>,           // 缓存的屏幕图像
+    image_failures: u32,                       // 生成缓存图像失败的次数
+    previous_rect: Option<Rect>,               // 上一次选区
+    last_tap: Option<(Instant, Point)>,        // 上一次轻点（按下后未拖动即抬起）的时间和位置
+    tapped_selection: Option<Rect>,            // 上一次轻点落在其中、随后被清除的选区
+    interpolation: Option<InterpolationMode>,  // 截图的插值方式（来自配置文件），None 表示自动
+    adjusting: Option<SelectionDrag>,          // 正在调整（缩放/移动）的已确定选区
+    label_rect: Option<Rect>,                  // 上一次绘制的尺寸标签区域（窗口坐标）
+    loupe_pos: Option<Point>,                  // 取色模式下放大镜跟随的鼠标位置
+    drawing: Option<Annotation>,               // 正在画的标注
+    mosaic_cache: Vec<(Rect, u32, PietImage)>, // 马赛克预览图像，按像素区域和块大小缓存
+    tracing_lasso: bool,                       // 是否正在描套索路径
+    transform: Affine,                         // 截图坐标到窗口坐标的变换（缩放和平移）
+    panning: Option<Point>,                    // 中键拖动平移时上一次的鼠标位置（窗口坐标）
+}
+
+/// 对已确定选区的一次拖动调整
+struct SelectionDrag {
+    handle: Handle,   // 抓住的控制点
+    start_rect: Rect, // 开始拖动时的选区
+    start_pos: Point, // 开始拖动时的鼠标位置
+}
+
+impl ScreenshotWidget {
+    /// 还没有任何选区、缩放和缓存的截图控件
+    fn new(theme: Theme, interpolation: Option<InterpolationMode>) -> Self {
+        ScreenshotWidget {
+            theme,
+            cached_image: None,
+            image_failures: 0,
+            previous_rect: None,
+            last_tap: None,
+            tapped_selection: None,
+            interpolation,
+            adjusting: None,
+            label_rect: None,
+            loupe_pos: None,
+            drawing: None,
+            mosaic_cache: Vec::new(),
+            tracing_lasso: false,
+            transform: Affine::IDENTITY,
+            panning: None,
+        }
+    }
+
+    /// 决定绘制截图时使用的插值方式
+    ///
+    /// 自动模式下，截图按 1:1 显示到物理像素上时用最近邻保持像素清晰，缩放显示时用双线性避免锯齿。
+    fn interpolation_for(&self, data: &AppState, size: Size) -> InterpolationMode {
+        self.interpolation.unwrap_or_else(|| {
+            let (w, h) = data.screenshot.dimensions();
+            if Size::new(w as f64, h as f64) == size * data.scale {
+                InterpolationMode::NearestNeighbor
+            } else {
+                InterpolationMode::Bilinear
+            }
+        })
+    }
+
+    /// 判断这次按下是否是在已有选区内的第二次轻点，是则返回该选区
+    fn take_double_tap(&mut self, pos: Point) -> Option<Rect> {
+        let (time, last_pos) = self.last_tap.take()?;
+        let rect = self.tapped_selection.take()?;
+        let is_double = time.elapsed() <= DOUBLE_TAP_INTERVAL
+            && (pos - last_pos).hypot() <= DOUBLE_TAP_DISTANCE
+            && rect.contains(pos);
+        is_double.then_some(rect)
+    }
+
+    /// 选区从 `old` 变为 `new` 时，只重绘受影响的区域；不知道旧选区时整屏重绘
+    ///
+    /// 边框、控制点和尺寸标签都画在窗口坐标下，范围也在窗口坐标下计算。
+    fn request_selection_paint(&self, ctx: &mut EventCtx, old: Option<Rect>, new: Rect) {
+        let old = old.map(|r| self.to_view(r));
+        match selection_dirty_rect(old, self.to_view(new), self.label_rect, &self.theme, ctx.size()) {
+            Some(dirty) => ctx.request_paint_rect(dirty),
+            None => ctx.request_paint(),
+        }
+    }
+
+    /// 截图坐标 `pos` 落在选区 `rect` 的哪个控制点上
+    ///
+    /// 控制点按屏幕上的大小判断，与缩放无关；锁定了宽高比时只有四角可以拖动。
+    fn hit_test(&self, data: &AppState, rect: Rect, pos: Point) -> Option<Handle> {
+        let handles = Handle::available(data.locked_ratio.is_some());
+        let (size, tolerance) = (self.theme.handle_size, self.theme.handle_tolerance);
+        hit_test(self.to_view(rect), self.transform * pos, handles, size, tolerance)
+    }
+
+    /// 截图坐标下的矩形在窗口中的位置
+    fn to_view(&self, rect: Rect) -> Rect {
+        self.transform.transform_rect_bbox(rect)
+    }
+
+    /// 在截图坐标 `pos` 处弹出右键菜单
+    fn show_menu(&self, ctx: &mut EventCtx, pos: Point) {
+        ctx.show_context_menu(make_context_menu(), self.transform * pos);
+    }
+
+    /// 当前的缩放倍数
+    fn zoom(&self) -> f64 {
+        self.transform.as_coeffs()[0]
+    }
+
+    /// 处理缩放和平移：滚轮以鼠标为中心缩放，中键拖动平移
+    ///
+    /// # 返回
+    /// 事件是否已被处理。
+    fn handle_view_event(&mut self, ctx: &mut EventCtx, event: &Event) -> bool {
+        match event {
+            Event::Wheel(e) if e.wheel_delta.y != 0.0 => {
+                let step = if e.wheel_delta.y < 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP };
+                let zoom = (self.zoom() * step).clamp(MIN_ZOOM, MAX_ZOOM);
+                let factor = zoom / self.zoom();
+                // 保持鼠标下的截图位置不动
+                let around = e.pos.to_vec2();
+                self.transform = Affine::translate(around) * Affine::scale(factor) * Affine::translate(-around) * self.transform;
+            }
+            Event::MouseDown(e) if e.button.is_middle() => {
+                self.panning = Some(e.pos);
+                ctx.set_active(true);
+            }
+            Event::MouseMove(e) if self.panning.is_some() => {
+                if let Some(last) = self.panning.replace(e.pos) {
+                    self.transform = Affine::translate(e.pos - last) * self.transform;
+                }
+                if !e.buttons.has_middle() {
+                    self.panning = None;
+                    ctx.set_active(false);
+                }
+            }
+            Event::MouseUp(e) if e.button.is_middle() && self.panning.is_some() => {
+                self.panning = None;
+                ctx.set_active(false);
+            }
+            _ => return false,
+        }
+        ctx.request_paint();
+        ctx.set_handled();
+        true
+    }
+
+    /// 把鼠标事件的位置从窗口坐标换算成截图坐标，其他事件返回 `None`
+    fn to_image_event(&self, event: &Event) -> Option<Event> {
+        let inverse = self.transform.inverse();
+        let map = |e: &MouseEvent| {
+            let mut e = e.clone();
+            e.pos = inverse * e.pos;
+            e
+        };
+        match event {
+            Event::MouseDown(e) => Some(Event::MouseDown(map(e))),
+            Event::MouseUp(e) => Some(Event::MouseUp(map(e))),
+            Event::MouseMove(e) => Some(Event::MouseMove(map(e))),
+            _ => None,
+        }
+    }
+
+    /// 结束对已确定选区的调整；没有实际移动时视为一次轻点，不弹出菜单
+    fn finish_adjusting(&mut self, ctx: &mut EventCtx, data: &AppState, pos: Point) {
+        ctx.set_active(false);
+        if let Some(drag) = self.adjusting.take() {
+            if data.selection_rect == Some(drag.start_rect) {
+                self.last_tap = Some((Instant::now(), pos));
+                self.tapped_selection = Some(drag.start_rect);
+            }
+        }
+    }
+
+    /// 绘制马赛克区域的预览，与 `crop_image` 导出时的处理相同
+    fn draw_mosaics(&mut self, ctx: &mut PaintCtx, data: &AppState) {
+        let (w, h) = data.screenshot.dimensions();
+        let bounds = Rect::new(0.0, 0.0, w as f64, h as f64);
+        let mut cache = std::mem::take(&mut self.mosaic_cache);
+        for region in data.annotations.iter().filter_map(Annotation::mosaic_region) {
+            let pixels = region.scale_from_origin(data.scale).round();
+            let visible = pixels.intersect(bounds);
+            if visible.width() <= 0.0 || visible.height() <= 0.0 {
+                continue;
+            }
+            let cached = cache.iter().position(|(r, b, _)| *r == pixels && *b == data.mosaic_block);
+            let image = match cached {
+                Some(i) => Some(cache.swap_remove(i).2),
+                None => {
+                    let (x, y) = (visible.x0 as u32, visible.y0 as u32);
+                    let mut sub = data.screenshot.crop_imm(x, y, visible.width() as u32, visible.height() as u32).to_rgba8();
+                    redact::apply_pixelation(&mut sub, pixels - visible.origin().to_vec2(), data.mosaic_block);
+                    let (sw, sh) = sub.dimensions();
+                    ctx.make_image(sw as usize, sh as usize, sub.as_raw(), druid::piet::ImageFormat::RgbaSeparate).ok()
+                }
+            };
+            if let Some(image) = image {
+                let dest = visible.scale_from_origin(1.0 / data.scale);
+                ctx.draw_image(&image, dest, InterpolationMode::NearestNeighbor);
+                self.mosaic_cache.push((pixels, data.mosaic_block, image));
+            }
+        }
+    }
+
+    /// 取消正在拖动或已确定的选区，回到初始状态
+    ///
+    /// # 返回
+    /// 是否有可取消的选区；没有时由调用方决定是否退出。
+    fn cancel_selection(&mut self, ctx: &mut EventCtx, data: &mut AppState) -> bool {
+        let active = data.is_selecting
+            || data.selection_rect.is_some()
+            || self.adjusting.is_some()
+            || self.drawing.is_some()
+            || self.tracing_lasso;
+        if !active {
+            return false;
+        }
+        data.is_selecting = false;
+        data.start_pos = Point::ZERO;
+        data.current_pos = Point::ZERO;
+        data.selection_rect = None;
+        data.annotations.clear();
+        data.lasso.clear();
+        // 清空 previous_rect，下一次拖动时整屏重绘，不会残留旧遮罩
+        self.previous_rect = None;
+        self.adjusting = None;
+        self.drawing = None;
+        self.tracing_lasso = false;
+        self.last_tap = None;
+        self.tapped_selection = None;
+        ctx.set_active(false);
+        ctx.request_paint();
+        true
+    }
+
+    /// 结束画标注：画出了东西才保留
+    fn finish_drawing(&mut self, ctx: &mut EventCtx, data: &mut AppState) {
+        ctx.set_active(false);
+        if let Some(annotation) = self.drawing.take().filter(|a| !a.is_empty()) {
+            data.annotations.push(annotation);
+        }
+        ctx.request_paint();
+    }
+
+    /// 结束描套索：围出的区域足够大时以外接矩形作为选区并弹出菜单，否则清除路径
+    fn finish_lasso(&mut self, ctx: &mut EventCtx, data: &mut AppState, pos: Point) {
+        self.tracing_lasso = false;
+        ctx.set_active(false);
+        if data.close_lasso().is_some() {
+            self.show_menu(ctx, pos);
+        }
+        ctx.request_paint();
+    }
+
+    /// 结束拖动选择：选区足够大时确定选区并弹出菜单，否则视为一次轻点
+    fn finish_selection(&mut self, ctx: &mut EventCtx, data: &mut AppState, pos: Point) {
+        ctx.set_active(false);
+        match data.release_selection(pos) {
+            Some(_) => self.show_menu(ctx, pos),
+            None => self.last_tap = Some((Instant::now(), pos)),
+        }
+        ctx.request_paint();
+    }
+
+    /// 窗口失去焦点时收不到后续的鼠标事件，把进行中的拖动按松开处理，
+    /// 但不弹出菜单（需要时按 Enter 或右键）
+    fn finish_on_focus_loss(&mut self, ctx: &mut EventCtx, data: &mut AppState) {
+        self.end_gestures(data);
+        ctx.set_active(false);
+        ctx.request_paint();
+    }
+
+    /// 结束所有进行中的拖动：选区按鼠标最后的位置确定，套索按已描的路径闭合，
+    /// 正在画的标注直接保留，调整选区和平移停在当前状态
+    fn end_gestures(&mut self, data: &mut AppState) {
+        if data.is_selecting {
+            data.release_selection(data.current_pos);
+        }
+        if self.tracing_lasso {
+            self.tracing_lasso = false;
+            data.close_lasso();
+        }
+        if let Some(annotation) = self.drawing.take().filter(|a| !a.is_empty()) {
+            data.annotations.push(annotation);
+        }
+        self.adjusting = None;
+        self.panning = None;
+    }
+
+    /// 在截图坐标下绘制截图、遮罩和标注
+    fn paint_image_space(&mut self, ctx: &mut PaintCtx, data: &AppState) {
+        let full_rect = data.bounds().to_rect();
+
+        // 首次绘制时生成缓存图像，失败时在后续绘制中重试几次
+        if self.cached_image.is_none() && self.image_failures < MAX_IMAGE_ATTEMPTS {
+            let (w, h) = data.screenshot.dimensions();
+            let buf = data.screenshot.to_rgba8();
+            match ctx.make_image(w as usize, h as usize, buf.as_raw(), druid::piet::ImageFormat::RgbaSeparate) {
+                Ok(img) => self.cached_image = Some(img),
+                Err(e) => {
+                    self.image_failures += 1;
+                    eprintln!("生成屏幕图像失败（第 {} 次）: {}", self.image_failures, e);
+                }
+            }
+        }
+        // 放大或缩小显示时改用双线性插值
+        let on_screen = full_rect.size() * self.zoom();
+        match &self.cached_image {
+            Some(img) => ctx.draw_image(img, full_rect, self.interpolation_for(data, on_screen)),
+            None => {
+                // 没有图像可画时用纯色背景代替，多次失败后提示用户
+                ctx.fill(full_rect, &Color::grey8(32));
+                if self.image_failures >= MAX_IMAGE_ATTEMPTS {
+                    draw_image_error(ctx, full_rect);
+                }
+            }
+        }
+
+        // 绘制选区遮罩
+        if let Some(r) = self.visible_selection(data) {
+            let mask = Color::rgba8(0, 0, 0, self.theme.mask_alpha);
+            ctx.fill(Rect::new(0.0, 0.0, full_rect.width(), r.y0), &mask);
+            ctx.fill(Rect::new(0.0, r.y1, full_rect.width(), full_rect.height()), &mask);
+            ctx.fill(Rect::new(0.0, r.y0, r.x0, r.y1), &mask);
+            ctx.fill(Rect::new(r.x1, r.y0, full_rect.width(), r.y1), &mask);
+            // 套索选区：外接矩形内、路径外的部分同样变暗
+            let lasso = data.lasso_path().filter(|_| data.selection_rect.is_some());
+            if let Some(path) = &lasso {
+                let mut outside = r.to_path(0.1);
+                outside.extend(path.iter());
+                ctx.fill_even_odd(outside, &mask);
+            }
+
+            // 标注只画在选区内，与导出的范围一致；标注属于截图内容，随缩放一起放大
+            if !data.annotations.is_empty() || self.drawing.is_some() {
+                let clip = lasso.unwrap_or_else(|| r.to_path(0.1));
+                ctx.with_save(|ctx| {
+                    ctx.clip(clip);
+                    self.draw_mosaics(ctx, data);
+                    for annotation in data.annotations.iter().chain(&self.drawing) {
+                        annotate::draw(ctx, annotation);
+                    }
+                });
+            }
+        } else {
+            ctx.fill(full_rect, &Color::rgba8(0, 0, 0, self.theme.idle_dim_alpha));
+        }
+    }
+
+    /// 正在拖动或已确定的选区
+    fn visible_selection(&self, data: &AppState) -> Option<Rect> {
+        data.selection_rect.or_else(|| data.is_selecting.then(|| data.get_current_selection()))
+    }
+
+    /// 在窗口坐标下绘制边框、控制点、尺寸标签和放大镜，它们的大小不随缩放变化
+    fn paint_chrome(&mut self, ctx: &mut PaintCtx, data: &AppState) {
+        let transform = self.transform;
+        let view_size = ctx.size();
+        let (color, width) = (&self.theme.border_color, self.theme.border_width);
+
+        if let Some(r) = self.visible_selection(data) {
+            let view = self.to_view(r);
+            let lasso = data.lasso_path().filter(|_| data.selection_rect.is_some()).map(|path| transform * path);
+            match &lasso {
+                Some(path) => ctx.stroke(path, color, width),
+                None => ctx.stroke(view, color, width),
+            }
+
+            // 正在拖出的马赛克区域先只画虚线框
+            if let Some(region) = self.drawing.as_ref().and_then(Annotation::mosaic_region) {
+                let clip = lasso.clone().unwrap_or_else(|| view.to_path(0.1));
+                let dashed = StrokeStyle::new().dash_pattern(&[4.0, 4.0]);
+                ctx.with_save(|ctx| {
+                    ctx.clip(clip);
+                    ctx.stroke_styled(self.to_view(region), &Color::WHITE, 1.0, &dashed);
+                });
+            }
+
+            // 已确定的矩形选区显示八个控制点
+            if !data.is_selecting && data.selection_rect.is_some() && lasso.is_none() {
+                for &handle in Handle::available(data.locked_ratio.is_some()) {
+                    let square = handle.square(view, self.theme.handle_size);
+                    ctx.fill(square, &Color::WHITE);
+                    ctx.stroke(square, &Color::rgba8(0, 0, 0, 160), 1.0);
+                }
+            }
+
+            // 安全区参考框（只用于取景，不会导出）
+            if data.show_guide {
+                let area = &data.safe_areas[data.guide_index % data.safe_areas.len()];
+                draw_safe_area_guide(ctx, view, &area.name, area.w, area.h);
+            }
+
+            self.label_rect = draw_dimension_label(ctx, data, r, view, view_size);
+        } else {
+            self.label_rect = None;
+        }
+
+        // 正在描的套索路径
+        if self.tracing_lasso && !data.lasso.is_empty() {
+            ctx.stroke(transform * polyline(&data.lasso, false), color, width);
+        }
+
+        if data.picking_color {
+            if let Some(pos) = self.loupe_pos {
+                draw_loupe(ctx, data, pos, loupe_rect(transform * pos, view_size));
+            }
+        }
+    }
 }
 
 impl Widget<AppState> for ScreenshotWidget {
     /// 处理鼠标和键盘事件，实现选区和菜单弹出
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, _env: &Env) {
+        if self.handle_view_event(ctx, event) {
+            return;
+        }
+        // 以下的鼠标位置都是截图坐标
+        let mapped = self.to_image_event(event);
+        let event = mapped.as_ref().unwrap_or(event);
         match event {
+            // 取色模式：单击复制该点颜色并退出，移动时放大镜跟随鼠标
+            Event::MouseDown(e) if data.picking_color && e.button.is_left() => {
+                if let Some(hex) = data.color_hex_at(e.pos) {
+                    if copy_text_to_clipboard(&hex).is_ok() {
+                        ctx.submit_command(druid::commands::QUIT_APP);
+                    }
+                }
+            }
+            Event::MouseMove(e) if data.picking_color => {
+                ctx.set_cursor(&Cursor::Crosshair);
+                // 放大镜画在窗口坐标下，大小不随缩放变化
+                let view = ctx.size();
+                if let Some(old) = self.loupe_pos.replace(e.pos) {
+                    ctx.request_paint_rect(loupe_rect(self.transform * old, view));
+                }
+                ctx.request_paint_rect(loupe_rect(self.transform * e.pos, view));
+            }
+
+            // 标注模式：在选区内拖动画出标注，选区外的操作不变
+            Event::MouseDown(e)
+                if e.button.is_left()
+                    && data.tool.is_some()
+                    && data.selection_rect.is_some_and(|r| r.contains(e.pos)) =>
+            {
+                if let Some(tool) = data.tool {
+                    self.drawing = Some(Annotation::start(tool, e.pos));
+                    ctx.set_active(true);
+                }
+            }
+            Event::MouseMove(e) if self.drawing.is_some() => {
+                if let Some(drawing) = &mut self.drawing {
+                    drawing.extend_to(e.pos);
+                }
+                // 左键已经在窗口外松开时按松开处理
+                if !e.buttons.has_left() {
+                    self.finish_drawing(ctx, data);
+                }
+                ctx.request_paint();
+            }
+            Event::MouseUp(e) if e.button.is_left() && self.drawing.is_some() => {
+                self.finish_drawing(ctx, data);
+            }
+            // 套索模式：按住左键描出闭合路径，松开后以它的外接矩形作为选区
+            Event::MouseDown(e) if e.button.is_left() && data.lasso_mode => {
+                data.selection_rect = None;
+                data.annotations.clear();
+                data.lasso = vec![e.pos];
+                self.tracing_lasso = true;
+                ctx.set_active(true);
+                ctx.request_paint();
+            }
+            Event::MouseMove(e) if self.tracing_lasso => {
+                let pos = clamp_to_size(e.pos, data.bounds());
+                if data.lasso.last().is_none_or(|last| (pos - *last).hypot() >= 1.0) {
+                    data.lasso.push(pos);
+                    ctx.request_paint();
+                }
+                if !e.buttons.has_left() {
+                    self.finish_lasso(ctx, data, pos);
+                }
+            }
+            Event::MouseUp(e) if e.button.is_left() && self.tracing_lasso => {
+                let pos = clamp_to_size(e.pos, data.bounds());
+                self.finish_lasso(ctx, data, pos);
+            }
+
             // 鼠标左键按下：
-            // - 单击：开始选择
-            // - 双击：截取全屏并复制
+            // - 在选区内双击（触屏双击）：在点击处弹出菜单
+            // - 其他双击：截取全屏并复制
+            // - 按在已确定选区的控制点上：缩放选区；按在选区内部：移动选区
+            // - 其他单击：开始新的选择
             Event::MouseDown(e) if e.button.is_left() => {
-                // 如果是双击 (或更多次点击)
-                if e.count >= 2 {
+                if let Some(rect) = self.take_double_tap(e.pos) {
+                    data.selection_rect = Some(rect);
+                    data.is_selecting = false;
+                    ctx.request_paint();
+                    self.show_menu(ctx, e.pos);
+                } else if e.count >= 2 {
                     // 将选区设置为整个屏幕
-                    data.selection_rect = Some(ctx.size().to_rect());
+                    data.selection_rect = Some(data.bounds().to_rect());
+                    data.lasso.clear();
                     // 裁剪图像
                     if let Some(img) = data.crop_image() {
                         // 复制到剪贴板并退出
-                        if copy_image_to_clipboard(&img).is_ok() {
+                        if copy_image_to_clipboard(&img, data.premultiply_clipboard).is_ok() {
                             ctx.submit_command(druid::commands::QUIT_APP);
                         }
                     }
                     // 阻止后续的拖动选择逻辑
                     data.is_selecting = false;
+                } else if let Some((rect, handle)) = data
+                    .selection_rect
+                    // 全屏选区（例如右键设置的）没有"外部"可点，拖动时总是开始新的选择；
+                    // 套索选区的外接矩形不能单独调整
+                    .filter(|r| *r != data.bounds().to_rect() && data.lasso.is_empty())
+                    .and_then(|r| self.hit_test(data, r, e.pos).map(|h| (r, h)))
+                {
+                    // 按在已确定选区的控制点或内部：开始缩放/移动
+                    self.adjusting = Some(SelectionDrag { handle, start_rect: rect, start_pos: e.pos });
+                    ctx.set_active(true);
                 } else {
                     // 如果是单击，执行原来的开始选择逻辑
-                    data.selection_rect = None;
+                    // 如果之前有选区（例如右键设置的全屏选区），旧遮罩可能覆盖整个屏幕，
+                    // 清空 previous_rect 让第一次拖动时整屏重绘，避免残留
+                    let had_selection = data.selection_rect.take().is_some();
+                    // 标注属于旧选区，重新选择时一并清除
+                    data.annotations.clear();
+                    data.lasso.clear();
                     data.is_selecting = true;
                     data.start_pos = e.pos;
                     data.current_pos = e.pos;
-                    self.previous_rect = Some(data.get_current_selection());
+                    // 拖动期间捕获鼠标，保证指针移出窗口后仍能收到 MouseUp
+                    ctx.set_active(true);
+                    self.previous_rect = if had_selection {
+                        None
+                    } else {
+                        Some(data.get_current_selection())
+                    };
                     ctx.request_paint();
                 }
             }
 
+            // 调整已确定的选区
+            Event::MouseMove(e) if self.adjusting.is_some() && !e.buttons.has_left() => {
+                self.finish_adjusting(ctx, data, e.pos);
+            }
+            Event::MouseMove(e) if self.adjusting.is_some() => {
+                if let Some(drag) = &self.adjusting {
+                    let old_rect = data.selection_rect.unwrap_or(drag.start_rect);
+                    let new_rect = data.drag_selection(drag.handle, drag.start_rect, e.pos - drag.start_pos);
+                    data.selection_rect = Some(new_rect);
+                    self.request_selection_paint(ctx, Some(old_rect), new_rect);
+                }
+            }
+            Event::MouseUp(e) if e.button.is_left() && self.adjusting.is_some() => {
+                self.finish_adjusting(ctx, data, e.pos);
+            }
+
+            // 悬停在已确定选区上时，按控制点切换光标
+            // 标注模式下在选区内显示十字光标
+            Event::MouseMove(e) if !data.is_selecting => {
+                let handle = data
+                    .selection_rect
+                    .filter(|_| data.lasso.is_empty())
+                    .and_then(|r| self.hit_test(data, r, e.pos));
+                match handle {
+                    Some(Handle::Move) if data.tool.is_some() => ctx.set_cursor(&Cursor::Crosshair),
+                    Some(handle) => ctx.set_cursor(&handle.cursor()),
+                    None => ctx.clear_cursor(),
+                }
+            }
+
             // 鼠标拖动：更新选择区域
+            // 如果左键已经松开却没有收到 MouseUp（例如在窗口外松开），按松开处理
+            Event::MouseMove(e) if data.is_selecting && !e.buttons.has_left() => {
+                let pos = clamp_to_size(e.pos, data.bounds());
+                self.finish_selection(ctx, data, pos);
+            }
             Event::MouseMove(e) if data.is_selecting => {
-                let old_rect = self.previous_rect.unwrap_or_else(|| data.get_current_selection());
-                data.current_pos = e.pos;
+                let old_rect = self.previous_rect;
+                data.current_pos = clamp_to_size(e.pos, data.bounds());
                 let new_rect = data.get_current_selection();
                 self.previous_rect = Some(new_rect);
 
-                // 只重绘变化的区域以提高性能
-                let dirty = old_rect.union(new_rect).inset(2.0);
-                ctx.request_paint_rect(dirty);
+                // 只重绘变化的区域以提高性能；不知道上一次绘制的选区时整屏重绘
+                self.request_selection_paint(ctx, old_rect, new_rect);
             }
             
             // 鼠标左键抬起：完成选择并显示菜单
             Event::MouseUp(e) if e.button.is_left() => {
                 if data.is_selecting {
-                    data.is_selecting = false; // 结束选择状态
+                    let pos = clamp_to_size(e.pos, data.bounds());
+                    self.finish_selection(ctx, data, pos);
+                }
+            }
 
-                    let sel = data.get_current_selection();
-                    // 只有当选区足够大时才认为是有效选择
-                    if sel.width() > 1.0 && sel.height() > 1.0 {
-                        data.selection_rect = Some(sel);
-                        ctx.show_context_menu(make_context_menu(), e.pos);
-                    } else {
-                        // 如果选区太小 (通常是单击而非拖动)，则清除选区
-                        data.selection_rect = None;
+            Event::WindowConnected => ctx.request_focus(),
+            // 例如拖动时按 Alt+Tab 或点了另一个显示器上的窗口：结束拖动，避免选区一直停在拖动状态。
+            // 控件内的键盘焦点（`LifeCycle::FocusChanged`）不会因此变化，要看窗口本身的焦点
+            Event::WindowLostFocus => {
+                self.finish_on_focus_loss(ctx, data);
+                ctx.set_handled();
+            }
+
+            // 钉在屏幕上：在原位置打开置顶的图片窗口，然后关闭截图窗口，
+            // 程序在钉图窗口关闭后才退出
+            Event::Command(cmd) if cmd.is(PIN_SELECTION) => {
+                if let (Some(img), Some(pos)) = (data.crop_image(), data.crop_origin()) {
+                    ctx.new_window(pin_window(img, pos, data.scale));
+                    ctx.submit_command(druid::commands::CLOSE_WINDOW);
+                }
+                ctx.set_handled();
+            }
+
+            // 方向键：移动选区 1 像素；Shift + 方向键：移动右下角 1 像素以放大/缩小选区，锁定了宽高比时另一边按比例跟随
+            // （按截图像素计，高缩放比例的屏幕上每次移动不到一个逻辑单位）
+            Event::KeyDown(k) if arrow_delta(&k.key).is_some() => {
+                let Some(old_rect) = data.selection_rect.filter(|_| !data.is_selecting && data.lasso.is_empty()) else {
+                    return;
+                };
+                let pixel = 1.0 / data.scale;
+                let delta = arrow_delta(&k.key).unwrap_or_default() * pixel;
+                let handle = if k.mods.shift() { Handle::BottomRight } else { Handle::Move };
+                let new_rect = data.drag_selection(handle, old_rect, delta);
+                if new_rect.width() >= pixel && new_rect.height() >= pixel {
+                    data.selection_rect = Some(new_rect);
+                    self.request_selection_paint(ctx, Some(old_rect), new_rect);
+                }
+                ctx.set_handled();
+            }
+
+            // Enter：复制当前选区（例如启动时预选的上一次选区）
+            Event::KeyDown(k) if k.key == Key::Enter => {
+                if data.selection_rect.is_some() && !data.is_selecting && perform_action(data, MenuAction::Copy) {
+                    ctx.submit_command(druid::commands::QUIT_APP);
+                }
+                ctx.set_handled();
+            }
+
+            // Esc / Backspace：取消当前选区；没有选区时 Esc 退出程序
+            Event::KeyDown(k) if matches!(k.key, Key::Escape | Key::Backspace) => {
+                if !self.cancel_selection(ctx, data) && k.key == Key::Escape {
+                    ctx.submit_command(druid::commands::QUIT_APP);
+                }
+                ctx.set_handled();
+            }
+
+            // Ctrl + Z：撤销上一个标注
+            Event::KeyDown(k) if k.mods.ctrl() && k.key == Key::Character("z".into()) => {
+                if data.annotations.pop().is_some() {
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+            }
+
+            // 字符快捷键：
+            // - g 灰度、s 锐化（开关）；b / B 增减亮度、c / C 增减对比度；r 清除所有滤镜
+            // - 1 矩形、2 箭头、3 画笔标注、4 马赛克（再按一次退出标注模式）；[ / ] 减小/增大马赛克块
+            // - i 进入/退出取色模式，l 进入/退出套索模式
+            // - 0 恢复原始大小和位置（滚轮缩放、中键拖动平移之后）
+            // - f 显示/隐藏安全区参考框，F 切换到下一个参考框
+            // - . 对当前选区重复上一次菜单操作
+            Event::KeyDown(k) => {
+                if let Key::Character(ch) = &k.key {
+                    match ch.as_str() {
+                        "." => {
+                            if let (Some(action), Some(_)) = (data.last_action, data.selection_rect) {
+                                if perform_action(data, action) {
+                                    ctx.submit_command(druid::commands::QUIT_APP);
+                                }
+                            }
+                        }
+                        "g" => data.edit_filters(|f| f.toggle(Filter::Grayscale)),
+                        "s" => data.edit_filters(|f| f.toggle(Filter::Sharpen { sigma: 1.0, threshold: 2 })),
+                        "b" => data.edit_filters(|f| f.adjust_brightness(10)),
+                        "B" => data.edit_filters(|f| f.adjust_brightness(-10)),
+                        "c" => data.edit_filters(|f| f.adjust_contrast(10.0)),
+                        "C" => data.edit_filters(|f| f.adjust_contrast(-10.0)),
+                        "r" => data.edit_filters(FilterChain::clear),
+                        "0" => self.transform = Affine::IDENTITY,
+                        "i" => data.picking_color = !data.picking_color,
+                        "l" => data.lasso_mode = !data.lasso_mode,
+                        "1" => data.toggle_tool(Tool::Rect),
+                        "2" => data.toggle_tool(Tool::Arrow),
+                        "3" => data.toggle_tool(Tool::Freehand),
+                        "4" => data.toggle_tool(Tool::Mosaic),
+                        "[" => data.mosaic_block = data.mosaic_block.saturating_sub(2).max(redact::MIN_BLOCK),
+                        "]" => data.mosaic_block = (data.mosaic_block + 2).min(redact::MAX_BLOCK),
+                        "f" => data.show_guide = !data.show_guide,
+                        "F" => {
+                            data.show_guide = true;
+                            data.guide_index = (data.guide_index + 1) % data.safe_areas.len();
+                        }
+                        _ => return,
                     }
-                    ctx.request_paint(); 
+                    ctx.request_paint();
+                    ctx.set_handled();
                 }
             }
 
             // 鼠标右键按下：直接显示菜单（全屏选区）
             Event::MouseDown(e) if e.button.is_right() => {
                 if data.selection_rect.is_none() {
-                    let screen_rect = ctx.size().to_rect();
+                    let screen_rect = data.bounds().to_rect();
                     data.selection_rect = Some(screen_rect);
                     ctx.request_paint();
                 }
-                ctx.show_context_menu(make_context_menu(), e.pos);
+                self.show_menu(ctx, e.pos);
             }
             
             _ => {}
@@ -161,126 +992,727 @@ impl Widget<AppState> for ScreenshotWidget {
     // 生命周期、更新、布局、绘制等方法
     fn lifecycle(
         &mut self,
-        _ctx: &mut LifeCycleCtx,
-        _event: &LifeCycle,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
         _data: &AppState,
         _env: &Env,
-    ) {}
+    ) {
+        // 注册焦点，以便接收键盘事件
+        if let LifeCycle::BuildFocusChain = event {
+            ctx.register_for_focus();
+        }
+    }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old: &AppState, data: &AppState, _env: &Env) {
         if !Arc::ptr_eq(&old.screenshot, &data.screenshot) {
             self.cached_image = None;
+            self.image_failures = 0;
+            self.mosaic_cache.clear();
             ctx.request_paint();
         }
     }
 
-    fn layout(&mut self, _ctx: &mut LayoutCtx, _bc: &BoxConstraints, data: &AppState, _env: &Env) -> Size {
-        let (w, h) = data.screenshot.dimensions();
-        Size::new(w as f64, h as f64)
+    /// 控件大小为截图换算成逻辑坐标后的大小，但不超过窗口（滚动截图的长图会被窗口截断）
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &AppState, _env: &Env) -> Size {
+        bc.constrain(logical_size(&data.screenshot, data.scale))
     }
 
-    /// 绘制截图和选区遮罩
+    /// 按当前的缩放和平移绘制截图和选区遮罩
     fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, _env: &Env) {
-        let size = ctx.size();
-        let full_rect = size.to_rect();
+        // 缩小或平移后露出的截图以外的区域
+        if self.transform != Affine::IDENTITY {
+            let view = ctx.size().to_rect();
+            ctx.fill(view, &Color::grey8(16));
+        }
+        let transform = self.transform;
+        ctx.with_save(|ctx| {
+            ctx.transform(transform);
+            self.paint_image_space(ctx, data);
+        });
+        self.paint_chrome(ctx, data);
+    }
+}
 
-        // 首次绘制时生成缓存图像
-        if self.cached_image.is_none() {
-            let (w, h) = data.screenshot.dimensions();
-            let buf = data.screenshot.to_rgba8();
-            self.cached_image = ctx
-                .make_image(w as usize, h as usize, buf.as_raw(), druid::piet::ImageFormat::RgbaSeparate)
-                .ok();
+/// 尺寸标签文字四周的留白
+const LABEL_PADDING: Size = Size::new(6.0, 3.0);
+/// 尺寸标签与选区边缘的距离
+const LABEL_GAP: f64 = 4.0;
+
+/// 选区从 `old` 变为 `new` 时需要重绘的区域（窗口坐标），`None` 表示整屏重绘
+///
+/// 范围包括新旧选区、画在边缘外的控制点，以及上一次（`label`）和这一次的尺寸标签。
+/// 新标签的大小在绘制前未知，按上一次的大小留出余量估算。
+/// `old` 为 `None` 表示不知道上一次绘制的选区，例如刚清除了右键设置的全屏选区：
+/// 那时整个屏幕都画着旧的遮罩，只重绘新旧选区附近会残留旧遮罩。
+fn selection_dirty_rect(old: Option<Rect>, new: Rect, label: Option<Rect>, theme: &Theme, bounds: Size) -> Option<Rect> {
+    let mut dirty = old?.union(new).inset(1.0 + theme.border_width + theme.handle_size);
+    if let Some(label) = label {
+        let predicted = label_box(new, label.size() + Size::new(40.0, 0.0), bounds);
+        dirty = dirty.union(label).union(predicted);
+    }
+    Some(dirty)
+}
+
+/// 计算尺寸标签的位置：优先放在选区左上角外侧，
+/// 上方放不下时放到选区下方，下方也放不下时放进选区内部；水平方向不超出屏幕
+fn label_box(sel: Rect, label: Size, bounds: Size) -> Rect {
+    let y = if sel.y0 - LABEL_GAP - label.height >= 0.0 {
+        sel.y0 - LABEL_GAP - label.height
+    } else if sel.y1 + LABEL_GAP + label.height <= bounds.height {
+        sel.y1 + LABEL_GAP
+    } else {
+        sel.y0 + LABEL_GAP
+    };
+    let x = sel.x0.min(bounds.width - label.width).max(0.0);
+    Rect::from_origin_size((x, y), label)
+}
+
+/// 在选区旁绘制 "宽 × 高 (x, y)" 尺寸标签，返回标签区域（窗口坐标）
+///
+/// 显示的是 `sel` 实际会被裁剪出的像素尺寸；锁定了宽高比时一并显示。
+/// 标签放在 `view`（选区在窗口中的位置）旁边，不超出 `view_size`。
+fn draw_dimension_label(ctx: &mut PaintCtx, data: &AppState, sel: Rect, view: Rect, view_size: Size) -> Option<Rect> {
+    let (w, h) = data.screenshot.dimensions();
+    let (x, y, cw, ch) = pixel_region(sel, data.scale, w, h)?;
+    let mut text = format!("{} × {}  ({}, {})", cw, ch, x, y);
+    if let Some((rw, rh)) = data.locked_ratio {
+        text.push_str(&format!("  [{}:{}]", rw, rh));
+    }
+
+    let layout = ctx
+        .text()
+        .new_text_layout(text)
+        .font(FontFamily::SYSTEM_UI, 12.0)
+        .text_color(Color::WHITE)
+        .build()
+        .ok()?;
+    let label = layout.size() + LABEL_PADDING * 2.0;
+    let bg = label_box(view, label, view_size);
+    ctx.fill(bg.to_rounded_rect(3.0), &Color::rgba8(0, 0, 0, 180));
+    ctx.draw_text(&layout, bg.origin() + LABEL_PADDING.to_vec2());
+    Some(bg)
+}
+
+/// 放大镜显示的像素半径（显示 (2r+1) x (2r+1) 个像素）
+const LOUPE_RADIUS: i64 = 5;
+/// 放大镜中每个像素的边长
+const LOUPE_ZOOM: f64 = 10.0;
+/// 放大镜像素网格的边长
+const LOUPE_SIDE: f64 = (2 * LOUPE_RADIUS + 1) as f64 * LOUPE_ZOOM;
+/// 放大镜下方颜色文字的高度
+const LOUPE_TEXT_HEIGHT: f64 = 20.0;
+/// 放大镜与鼠标的距离
+const LOUPE_OFFSET: f64 = 20.0;
+
+/// 放大镜区域（窗口坐标）：默认在鼠标右下方，靠近窗口边缘时翻到另一侧
+fn loupe_rect(pos: Point, bounds: Size) -> Rect {
+    let size = Size::new(LOUPE_SIDE, LOUPE_SIDE + LOUPE_TEXT_HEIGHT);
+    let x = if pos.x + LOUPE_OFFSET + size.width <= bounds.width {
+        pos.x + LOUPE_OFFSET
+    } else {
+        pos.x - LOUPE_OFFSET - size.width
+    };
+    let y = if pos.y + LOUPE_OFFSET + size.height <= bounds.height {
+        pos.y + LOUPE_OFFSET
+    } else {
+        pos.y - LOUPE_OFFSET - size.height
+    };
+    Rect::from_origin_size((x, y), size)
+}
+
+/// 在 `rect`（窗口坐标，见 `loupe_rect`）中绘制取色放大镜：
+/// 截图坐标 `pos` 周围像素的放大网格，以及当前像素的颜色值
+fn draw_loupe(ctx: &mut PaintCtx, data: &AppState, pos: Point, rect: Rect) {
+    let (Some((cx, cy)), Some(hex)) = (data.pixel_at(pos), data.color_hex_at(pos)) else {
+        return;
+    };
+    let (w, h) = data.screenshot.dimensions();
+    ctx.fill(rect, &Color::rgba8(0, 0, 0, 200));
+
+    for dy in -LOUPE_RADIUS..=LOUPE_RADIUS {
+        for dx in -LOUPE_RADIUS..=LOUPE_RADIUS {
+            let (px, py) = (cx as i64 + dx, cy as i64 + dy);
+            if px < 0 || py < 0 || px >= w as i64 || py >= h as i64 {
+                continue;
+            }
+            let Rgba([r, g, b, _]) = data.screenshot.get_pixel(px as u32, py as u32);
+            let offset = ((dx + LOUPE_RADIUS) as f64 * LOUPE_ZOOM, (dy + LOUPE_RADIUS) as f64 * LOUPE_ZOOM);
+            let cell = Rect::from_origin_size(rect.origin() + offset, (LOUPE_ZOOM, LOUPE_ZOOM));
+            ctx.fill(cell, &Color::rgb8(r, g, b));
+        }
+    }
+
+    // 标出当前像素
+    let center = LOUPE_RADIUS as f64 * LOUPE_ZOOM;
+    let cell = Rect::from_origin_size(rect.origin() + (center, center), (LOUPE_ZOOM, LOUPE_ZOOM));
+    ctx.stroke(cell, &Color::WHITE, 1.0);
+    ctx.stroke(rect, &Color::WHITE, 1.0);
+
+    if let Ok(layout) = ctx
+        .text()
+        .new_text_layout(hex)
+        .font(FontFamily::MONOSPACE, 12.0)
+        .text_color(Color::WHITE)
+        .build()
+    {
+        let text_y = rect.y0 + LOUPE_SIDE + (LOUPE_TEXT_HEIGHT - layout.size().height) / 2.0;
+        ctx.draw_text(&layout, (rect.x0 + 4.0, text_y));
+    }
+}
+
+/// 内置的社交平台/移动端安全区：名称和宽高比
+const SAFE_AREAS: [(&str, u32, u32); 5] = [
+    ("Twitter 16:9", 16, 9),
+    ("Instagram 1:1", 1, 1),
+    ("Instagram 4:5", 4, 5),
+    ("Story 9:16", 9, 16),
+    ("Facebook 1.91:1", 191, 100),
+];
+
+/// 可选的安全区参考框：内置的在前，配置文件中追加的（`extra`）在后；宽或高为 0 的会被忽略
+fn safe_areas(extra: &[SafeArea]) -> Vec<SafeArea> {
+    let builtin = SAFE_AREAS.iter().map(|&(name, w, h)| SafeArea { name: name.to_string(), w, h });
+    builtin.chain(extra.iter().filter(|a| a.w > 0 && a.h > 0).cloned()).collect()
+}
+
+/// 在选区中居中绘制指定宽高比的最大参考框
+fn draw_safe_area_guide(ctx: &mut PaintCtx, sel: Rect, name: &str, rw: u32, rh: u32) {
+    let ratio = rw as f64 / rh as f64;
+    let (w, h) = if sel.width() / sel.height().max(1.0) > ratio {
+        (sel.height() * ratio, sel.height())
+    } else {
+        (sel.width(), sel.width() / ratio)
+    };
+    let frame = Rect::from_center_size(sel.center(), (w, h));
+    let color = Color::rgb8(0, 200, 255);
+    ctx.stroke_styled(frame, &color, 1.0, &StrokeStyle::new().dash_pattern(&[6.0, 4.0]));
+
+    if let Ok(layout) = ctx.text().new_text_layout(name.to_string()).text_color(color).build() {
+        ctx.draw_text(&layout, frame.origin() + (4.0, 2.0));
+    }
+}
+
+/// 依次连接各点的折线，`closed` 为真时首尾相连
+fn polyline(points: &[Point], closed: bool) -> BezPath {
+    let mut path = BezPath::new();
+    if let Some((first, rest)) = points.split_first() {
+        path.move_to(*first);
+        for p in rest {
+            path.line_to(*p);
         }
-        if let Some(img) = &self.cached_image {
-            ctx.draw_image(img, full_rect, druid::piet::InterpolationMode::NearestNeighbor);
+        if closed {
+            path.close_path();
         }
+    }
+    path
+}
 
-        // 绘制选区遮罩和边框
-        let sel_rect = data.selection_rect.or_else(|| {
-            if data.is_selecting {
-                Some(data.get_current_selection())
-            } else {
+/// 截图换算成逻辑坐标后的大小
+fn logical_size(image: &DynamicImage, scale: f64) -> Size {
+    let (w, h) = image.dimensions();
+    Size::new(w as f64 / scale, h as f64 / scale)
+}
+
+/// 方向键对应的 1 像素位移，其他按键返回 `None`
+fn arrow_delta(key: &Key) -> Option<Vec2> {
+    match key {
+        Key::ArrowLeft => Some(Vec2::new(-1.0, 0.0)),
+        Key::ArrowRight => Some(Vec2::new(1.0, 0.0)),
+        Key::ArrowUp => Some(Vec2::new(0.0, -1.0)),
+        Key::ArrowDown => Some(Vec2::new(0.0, 1.0)),
+        _ => None,
+    }
+}
+
+/// 把点限制在控件范围内（拖到窗口外时选区停在边缘）
+fn clamp_to_size(pos: Point, size: Size) -> Point {
+    Point::new(pos.x.clamp(0.0, size.width), pos.y.clamp(0.0, size.height))
+}
+
+/// 在屏幕中央绘制截图无法显示的提示
+fn draw_image_error(ctx: &mut PaintCtx, full_rect: Rect) {
+    let layout = ctx
+        .text()
+        .new_text_layout("无法显示截图：图像创建失败（可能是内存不足或显卡驱动问题）")
+        .font(FontFamily::SYSTEM_UI, 18.0)
+        .text_color(Color::WHITE)
+        .build();
+    if let Ok(layout) = layout {
+        let origin = full_rect.center() - layout.size().to_vec2() / 2.0;
+        ctx.draw_text(&layout, origin);
+    }
+}
+
+// ----------- 菜单动作 -----------
+/// 可被 "重复上一次操作" 再次执行的菜单动作
+#[derive(Clone, Copy, PartialEq, Data)]
+enum MenuAction {
+    Copy,        // 复制
+    SaveAs,      // 另存为
+    CopyAndSave, // 复制并保存到图片文件夹
+    ScanQrCode,  // 扫描二维码
+}
+
+/// 对当前选区执行菜单动作，并记为上一次操作
+///
+/// # 返回
+/// 动作完成后是否应退出程序。
+fn perform_action(data: &mut AppState, action: MenuAction) -> bool {
+    data.last_action = Some(action);
+    let Some(img) = data.crop_image() else {
+        return false;
+    };
+    match action {
+        MenuAction::Copy => {
+            if copy_image_to_clipboard(&img, data.premultiply_clipboard).is_ok() {
+                data.remember_selection();
+            }
+            true
+        }
+        MenuAction::SaveAs => match ask_save_path() {
+            Some(path) => save_to(data, &img, &path),
+            None => false,
+        },
+        // 不询问文件名，按配置中的模板直接保存到图片文件夹；找不到图片文件夹时改为弹出保存对话框
+        MenuAction::CopyAndSave => {
+            if let Err(e) = copy_image_to_clipboard(&img, data.premultiply_clipboard) {
+                MessageDialog::new().set_title("错误").set_description(&format!("复制失败: {}", e)).show();
+                return false;
+            }
+            let fields = NameFields::now(&data.app_name, img.width(), img.height());
+            let path = default_save_path(&data.save_name, &fields).unwrap_or_else(|e| {
+                MessageDialog::new().set_title("错误").set_description(&format!("无法生成文件名: {}", e)).show();
                 None
+            });
+            match path.or_else(ask_save_path) {
+                Some(path) => save_to(data, &img, &path),
+                None => false,
             }
-        });
+        }
+        // 识别到多个码时每行一个
+        MenuAction::ScanQrCode => match scan_all_codes(&img) {
+            Ok(codes) if !codes.is_empty() => confirm_scan_result(&codes.join("\n")),
+            Ok(_) => { MessageDialog::new().set_title("提示").set_description("未扫描到二维码").show(); false }
+            Err(e) => { MessageDialog::new().set_title("错误").set_description(&format!("扫描失败: {}", e)).show(); false }
+        },
+    }
+}
 
-        if let Some(r) = sel_rect {
-            let mask = Color::rgba8(0, 0, 0, 128);
-            ctx.fill(Rect::new(0.0, 0.0, full_rect.width(), r.y0), &mask);
-            ctx.fill(Rect::new(0.0, r.y1, full_rect.width(), full_rect.height()), &mask);
-            ctx.fill(Rect::new(0.0, r.y0, r.x0, r.y1), &mask);
-            ctx.fill(Rect::new(r.x1, r.y0, full_rect.width(), r.y1), &mask);
-            ctx.stroke(r, &Color::WHITE, 1.0);
-        } else {
-            ctx.fill(full_rect, &Color::rgba8(0, 0, 0, 72));
+/// 弹出保存对话框；没有输入扩展名时默认保存为 PNG
+fn ask_save_path() -> Option<PathBuf> {
+    let dialog = SAVE_FORMATS
+        .iter()
+        .fold(rfd::FileDialog::new(), |dialog, (name, exts)| dialog.add_filter(*name, exts));
+    let mut path = dialog.save_file()?;
+    if path.extension().is_none() {
+        path.set_extension("png");
+    }
+    Some(path)
+}
+
+/// 保存图片，失败时弹窗提示；开启了相应选项时在文件管理器中显示
+///
+/// # 返回
+/// 是否保存成功（成功后应退出程序）。
+fn save_to(data: &AppState, img: &ImageBuffer<Rgba<u8>, Vec<u8>>, path: &Path) -> bool {
+    let saved = match path.parent() {
+        Some(dir) => std::fs::create_dir_all(dir).map_err(anyhow::Error::from),
+        None => Ok(()),
+    }
+    .and_then(|()| save_image(img, path));
+    if let Err(e) = saved {
+        MessageDialog::new().set_title("错误").set_description(&format!("保存失败: {}", e)).show();
+        return false;
+    }
+    data.remember_selection();
+    if data.reveal_after_save {
+        if let Err(e) = reveal_in_file_manager(path) {
+            MessageDialog::new().set_title("错误").set_description(&format!("无法打开文件夹: {}", e)).show();
         }
+    }
+    true
+}
+
+/// 扫描结果确认对话框的按钮文字
+const COPY_AND_CLOSE: &str = "复制并关闭";
+const CANCEL: &str = "取消";
+
+/// 显示识别出的内容，由用户决定是否复制
+///
+/// # 返回
+/// 用户是否选择了复制（复制后应退出程序）。
+fn confirm_scan_result(text: &str) -> bool {
+    let result = MessageDialog::new()
+        .set_title("扫描结果")
+        .set_description(text)
+        .set_buttons(MessageButtons::OkCancelCustom(COPY_AND_CLOSE.into(), CANCEL.into()))
+        .show();
+    // 部分平台对自定义按钮返回 Ok，其余返回按钮文字
+    let confirmed = result == MessageDialogResult::Ok || result == MessageDialogResult::Custom(COPY_AND_CLOSE.into());
+    if confirmed {
+        copy_text_to_clipboard(text).ok();
+    }
+    confirmed
+}
 
+/// 把剪贴板中的文本生成二维码图片，再放回剪贴板
+///
+/// # 返回
+/// 是否成功（成功后应退出程序）；失败时已经弹窗提示。
+fn generate_qr_from_clipboard() -> bool {
+    let text = Clipboard::new().and_then(|mut clipboard| clipboard.get_text());
+    let text = match text {
+        Ok(text) if !text.trim().is_empty() => text,
+        _ => {
+            MessageDialog::new().set_title("提示").set_description("剪贴板中没有文本").show();
+            return false;
+        }
+    };
+    // 二维码完全不透明，是否预乘没有区别
+    match generate_qr(&text).and_then(|img| copy_image_to_clipboard(&img, false)) {
+        Ok(()) => true,
+        Err(e) => {
+            MessageDialog::new().set_title("错误").set_description(&format!("生成二维码失败: {}", e)).show();
+            false
+        }
     }
 }
 
 // ----------- 右键菜单生成 -----------
+/// 可锁定的选区宽高比
+const ASPECT_RATIOS: [(u32, u32); 4] = [(1, 1), (4, 3), (3, 2), (16, 9)];
+
+/// "锁定比例" 子菜单：选中后之后的每次拖动都保持该比例
+fn make_ratio_menu() -> Menu<AppState> {
+    let free = MenuItem::new("自由")
+        .on_activate(|_ctx, data: &mut AppState, _| data.locked_ratio = None)
+        .selected_if(|data: &AppState, _| data.locked_ratio.is_none());
+    ASPECT_RATIOS.iter().fold(Menu::new("锁定比例").entry(free), |menu, &ratio| {
+        menu.entry(
+            MenuItem::new(format!("{}:{}", ratio.0, ratio.1))
+                .on_activate(move |_ctx, data: &mut AppState, _| data.locked_ratio = Some(ratio))
+                .selected_if(move |data: &AppState, _| data.locked_ratio == Some(ratio)),
+        )
+    })
+}
+
+/// 生成执行菜单动作的菜单项，动作要求退出时退出程序
+fn action_item(label: &'static str, action: MenuAction) -> MenuItem<AppState> {
+    MenuItem::new(label).on_activate(move |ctx: &mut MenuEventCtx, data: &mut AppState, _| {
+        if perform_action(data, action) {
+            ctx.submit_command(druid::commands::QUIT_APP);
+        }
+    })
+}
+
 fn make_context_menu() -> Menu<AppState> {
     Menu::empty()
-        .entry(MenuItem::new("复制").on_activate(|ctx: &mut MenuEventCtx, data: &mut AppState, _| {
-            if let Some(img) = data.crop_image() {
-                copy_image_to_clipboard(&img).ok();
+        .entry(action_item("复制", MenuAction::Copy))
+        .entry(action_item("另存为...", MenuAction::SaveAs))
+        .entry(action_item("复制并保存", MenuAction::CopyAndSave))
+        .entry(action_item("扫描二维码", MenuAction::ScanQrCode))
+        .entry(MenuItem::new("生成二维码").on_activate(|ctx, _data: &mut AppState, _| {
+            if generate_qr_from_clipboard() {
                 ctx.submit_command(druid::commands::QUIT_APP);
             }
         }))
-        .entry(MenuItem::new("另存为...").on_activate(|ctx, data: &mut AppState, _| {
-            if let Some(img) = data.crop_image() {
-                if let Some(path) = rfd::FileDialog::new().add_filter("PNG", &["png"]).save_file() {
-                    img.save(&path).ok();
-                    ctx.submit_command(druid::commands::QUIT_APP);
-                }
-            }
-        }))
-        .entry(MenuItem::new("扫描二维码").on_activate(|ctx, data: &mut AppState, _| {
-            if let Some(img) = data.crop_image() {
-                match scan_qr_code(&img) {
-                    Ok(Some(txt)) => { copy_text_to_clipboard(&txt).ok(); ctx.submit_command(druid::commands::QUIT_APP); }
-                    Ok(None) => { MessageDialog::new().set_title("提示").set_description("未扫描到二维码").show(); }
-                    Err(e) => { MessageDialog::new().set_title("错误").set_description(&format!("扫描失败: {}", e)).show(); }
-                }
-            }
-        }))
+        .entry(MenuItem::new("钉在屏幕上").on_activate(|ctx, _data: &mut AppState, _| ctx.submit_command(PIN_SELECTION)))
+        .entry(
+            MenuItem::new("马赛克")
+                .on_activate(|_ctx, data: &mut AppState, _| data.toggle_tool(Tool::Mosaic))
+                .selected_if(|data: &AppState, _| data.tool == Some(Tool::Mosaic)),
+        )
+        .entry(
+            MenuItem::new("套索选择")
+                .on_activate(|_ctx, data: &mut AppState, _| data.lasso_mode = !data.lasso_mode)
+                .selected_if(|data: &AppState, _| data.lasso_mode),
+        )
+        .entry(MenuItem::new("取色").on_activate(|_ctx, data: &mut AppState, _| data.picking_color = true))
+        .entry(make_ratio_menu())
+        .entry(
+            MenuItem::new("保存后打开所在文件夹")
+                .on_activate(|_ctx, data: &mut AppState, _| {
+                    data.reveal_after_save = !data.reveal_after_save;
+                    // 写进配置文件，之后每次启动都保持这个选择
+                    let reveal = data.reveal_after_save;
+                    if let Err(e) = config::update(|c| c.reveal_after_save = reveal) {
+                        eprintln!("保存配置失败: {:#}", e);
+                    }
+                })
+                .selected_if(|data: &AppState, _| data.reveal_after_save),
+        )
         .entry(MenuItem::new("退出").on_activate(|ctx, _, _| ctx.submit_command(druid::commands::QUIT_APP)))
 }
 
 // ----------- 程序入口 -----------
-// ----------- 程序入口 -----------
+/// 启动阶段出错时弹窗说明原因并以非零状态退出
+///
+/// 程序没有控制台窗口，直接返回错误时用户什么也看不到，像是静默崩溃。
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    MessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title("rsqs 无法启动")
+        .set_description(message)
+        .show();
+    std::process::exit(1);
+}
+
 fn main() -> Result<()> {
-    // 捕获主显示器屏幕
-    let mons = Monitor::all()?;
-    let mon = mons.get(0).ok_or_else(|| anyhow::anyhow!("找不到显示器"))?;
-    let img = mon.capture_image()?;
-    let (w, h) = (img.width(), img.height());
-    let raw = img.into_raw();
-    let buf = image::ImageBuffer::from_raw(w, h, raw).ok_or_else(|| anyhow::anyhow!("转换失败"))?;
-    let dyn_img = DynamicImage::ImageRgba8(buf);
+    let options = parse_args(std::env::args().skip(1)).unwrap_or_else(|e| exit_with_error(&format!("命令行参数错误: {}", e)));
+    if let Some(delay) = options.delay {
+        std::thread::sleep(delay);
+    }
+    // 截图前位于前台的程序，用于文件名模板中的 {app}；截图窗口打开后前台就是 rsqs 自己了
+    let app_name = active_app_name().unwrap_or_default();
 
+    // 截取指定的显示器，未指定时截取鼠标所在的显示器
+    let mons = Monitor::all().unwrap_or_else(|e| {
+        exit_with_error(&format!(
+            "无法获取显示器列表: {}\n\n如果处于锁屏、远程桌面或 Wayland 会话中，请确认允许截图后重试。",
+            e
+        ))
+    });
+    if mons.is_empty() {
+        exit_with_error("没有找到任何显示器。\n\n请确认显示器已连接，并且是在图形桌面中运行本程序。");
+    }
+    // 权限、驱动、显示器序号等问题各自的处理建议已经包含在错误信息中
+    let (dyn_img, origin, scale) = capture_target(&mons, options.monitor)
+        .unwrap_or_else(|e| exit_with_error(&format!("截图失败: {}", e)));
+    // 窗口始终与显示器一样大；滚动截图拼出的长图会比窗口高，只截活动窗口时截图画在窗口左上角
+    let size = logical_size(&dyn_img, scale);
+    let dyn_img = if options.scroll {
+        capture_scrolling(dyn_img, &mons, options.monitor)
+            .unwrap_or_else(|e| exit_with_error(&format!("滚动截图失败: {}", e)))
+    } else if options.active_window {
+        match options.region_rel {
+            Some(region) => capture_active_window_region(region),
+            None => capture_active_window(),
+        }
+        .unwrap_or_else(|e| exit_with_error(&format!("活动窗口截图失败: {}", e)))
+    } else {
+        dyn_img
+    };
+    // 预选上一次的选区（已裁剪到当前截图范围内）
+    let (w, h) = dyn_img.dimensions();
+    let selection_rect = last_selection::load(w, h).map(|r| {
+        let origin = Point::new(r.x as f64 / scale, r.y as f64 / scale);
+        Rect::from_origin_size(origin, (r.width as f64 / scale, r.height as f64 / scale))
+    });
+
+
+    // 配置文件有误时提示后按默认配置继续，不影响截图
+    let mut config = config::load().unwrap_or_else(|e| {
+        eprintln!("读取配置失败: {:#}", e);
+        MessageDialog::new()
+            .set_level(rfd::MessageLevel::Warning)
+            .set_title("配置文件错误")
+            .set_description(&format!("{:#}\n\n本次将使用默认配置。", e))
+            .show();
+        config::Config::default()
+    });
+    // 文件名模板要到保存时才用到，启动时先检查，免得截完图才发现保存不了
+    if let Err(e) = check_name_template(&config.save_name) {
+        eprintln!("文件名模板有误: {}", e);
+        MessageDialog::new()
+            .set_level(rfd::MessageLevel::Warning)
+            .set_title("配置文件错误")
+            .set_description(&format!("save_name: {}\n\n本次将使用默认的文件名。", e))
+            .show();
+        config.save_name = DEFAULT_NAME_TEMPLATE.to_string();
+    }
 
     // 初始化应用状态和窗口
-    let init = AppState { 
-        screenshot: Arc::new(dyn_img), 
+    let screenshot = Arc::new(dyn_img);
+    let mut init = AppState { 
+        screenshot: screenshot.clone(), 
+        original: screenshot, 
+        filters: config.filters, 
+        origin, 
+        scale, 
         is_selecting: false, 
         start_pos: Point::ZERO, 
         current_pos: Point::ZERO, 
-        selection_rect: None 
+        selection_rect, 
+        locked_ratio: None, 
+        reveal_after_save: config.reveal_after_save, 
+        premultiply_clipboard: config.premultiply_clipboard, 
+        save_name: config.save_name, 
+        app_name, 
+        show_guide: false, 
+        guide_index: 0, 
+        safe_areas: Arc::new(safe_areas(&config.safe_areas)), 
+        last_action: None, 
+        picking_color: false, 
+        annotations: Vec::new(), 
+        tool: None, 
+        mosaic_block: redact::DEFAULT_BLOCK, 
+        lasso_mode: false, 
+        lasso: Vec::new() 
     };
+    // 配置中的滤镜在第一次绘制前就应用好
+    init.apply_filters();
+
+    // 控制点太小时无法抓取，配置有误时至少保留 1 像素
+    let theme = Theme {
+        handle_size: config.handle_size.max(1.0),
+        handle_tolerance: config.handle_tolerance.max(0.0),
+        ..Theme::default()
+    };
+    let widget = ScreenshotWidget::new(theme, config.interpolation.map(InterpolationMode::from));
 
     // 使用构建者模式创建窗口描述
-    let window = WindowDesc::new(ScreenshotWidget { cached_image: None, previous_rect: None })
-        .window_size((w as f64, h as f64))
+    // 创建时就指定位置、大小和无边框，避免窗口先出现在系统默认位置再跳到显示器原点
+    // Esc 由截图控件处理：有选区时先取消选区，没有时才退出
+    let window = WindowDesc::new(widget)
+        .set_position(init.origin)
+        .window_size(size)
         .show_titlebar(false)
-        .resizable(false) // <-- 这里没有分号
-        .menu(|_window_id, _app_state, _env| {
-            let quit_menu_item = MenuItem::<AppState>::new("退出")
-                .hotkey(SysMods::None, Key::Escape)
-                .command(druid::commands::QUIT_APP);
-            Menu::new("AppMenu").entry(quit_menu_item)
-        });
+        .resizable(false);
     AppLauncher::with_window(window).launch(init)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Size = Size::new(1920.0, 1080.0);
+
+    /// 截图为 `width` x `height` 像素、缩放比例为 1 的初始状态
+    fn state(width: u32, height: u32) -> AppState {
+        let screenshot = Arc::new(DynamicImage::new_rgba8(width, height));
+        AppState {
+            screenshot: screenshot.clone(),
+            original: screenshot,
+            filters: FilterChain::default(),
+            origin: Point::ZERO,
+            scale: 1.0,
+            is_selecting: false,
+            start_pos: Point::ZERO,
+            current_pos: Point::ZERO,
+            selection_rect: None,
+            locked_ratio: None,
+            reveal_after_save: false,
+            premultiply_clipboard: false,
+            save_name: DEFAULT_NAME_TEMPLATE.to_string(),
+            app_name: String::new(),
+            show_guide: false,
+            guide_index: 0,
+            safe_areas: Arc::new(safe_areas(&[])),
+            last_action: None,
+            picking_color: false,
+            annotations: Vec::new(),
+            tool: None,
+            mosaic_block: redact::DEFAULT_BLOCK,
+            lasso_mode: false,
+            lasso: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn release_outside_window_clamps_to_edge() {
+        let mut data = state(200, 100);
+        data.is_selecting = true;
+        data.start_pos = Point::new(50.0, 50.0);
+        data.current_pos = Point::new(150.0, 20.0);
+        // 在窗口右上方之外松开：选区停在截图边缘，并且不再处于拖动状态
+        let sel = data.release_selection(Point::new(500.0, -30.0));
+        assert_eq!(sel, Some(Rect::new(50.0, 0.0, 200.0, 50.0)));
+        assert_eq!(data.selection_rect, sel);
+        assert!(!data.is_selecting);
+    }
+
+    #[test]
+    fn release_without_drag_clears_selection() {
+        let mut data = state(200, 100);
+        data.is_selecting = true;
+        data.start_pos = Point::new(50.0, 50.0);
+        data.selection_rect = Some(Rect::new(0.0, 0.0, 10.0, 10.0));
+        assert_eq!(data.release_selection(Point::new(50.5, 50.5)), None);
+        assert_eq!(data.selection_rect, None);
+        assert!(!data.is_selecting);
+    }
+
+    #[test]
+    fn locked_ratio_stays_inside_bounds() {
+        let mut data = state(200, 100);
+        data.locked_ratio = Some((1, 1));
+        // 向右下拖动：按宽度放大后高度会超出底边，两边一起缩到 40 x 40
+        data.start_pos = Point::new(150.0, 60.0);
+        data.current_pos = Point::new(200.0, 70.0);
+        assert_eq!(data.get_current_selection(), Rect::new(150.0, 60.0, 190.0, 100.0));
+        // 向左上拖动同样限制在截图内
+        data.locked_ratio = Some((16, 9));
+        data.start_pos = Point::new(40.0, 90.0);
+        data.current_pos = Point::new(0.0, 0.0);
+        let sel = data.get_current_selection();
+        assert_eq!(sel.x0, 0.0);
+        assert!(sel.y0 >= 0.0);
+        assert!((sel.width() / sel.height() - 16.0 / 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn full_screen_to_new_drag_repaints_everything() {
+        // 右键设置全屏选区后开始新的拖动：MouseDown 清空了 previous_rect，应整屏重绘
+        let tiny = Rect::new(100.0, 100.0, 101.0, 101.0);
+        assert_eq!(selection_dirty_rect(None, tiny, None, &Theme::default(), SCREEN), None);
+
+        // 即使仍记着旧的全屏选区，重绘范围也要覆盖整个屏幕
+        let full = SCREEN.to_rect();
+        let label = Some(Rect::new(0.0, 0.0, 120.0, 20.0));
+        let dirty = selection_dirty_rect(Some(full), tiny, label, &Theme::default(), SCREEN).unwrap();
+        assert_eq!(dirty.union(full), dirty);
+    }
+
+    #[test]
+    fn small_drag_repaints_only_nearby() {
+        let old = Rect::new(100.0, 100.0, 200.0, 200.0);
+        let new = Rect::new(100.0, 100.0, 210.0, 205.0);
+        let dirty = selection_dirty_rect(Some(old), new, None, &Theme::default(), SCREEN).unwrap();
+        assert_eq!(dirty.union(old.union(new)), dirty);
+        assert!(dirty.width() < 200.0 && dirty.height() < 200.0);
+    }
+
+    #[test]
+    fn configured_safe_areas_follow_builtin_ones() {
+        let extra = [
+            SafeArea { name: "Banner 3:1".to_string(), w: 3, h: 1 },
+            SafeArea { name: "Broken".to_string(), w: 0, h: 1 },
+        ];
+        let areas = safe_areas(&extra);
+        assert_eq!(areas.len(), SAFE_AREAS.len() + 1);
+        assert_eq!(areas[0].name, SAFE_AREAS[0].0);
+        assert_eq!(areas.last(), Some(&extra[0]));
+    }
+
+    #[test]
+    fn focus_loss_ends_every_gesture() {
+        let mut widget = ScreenshotWidget::new(Theme::default(), None);
+
+        // 拖动选区时：按鼠标最后的位置确定选区
+        let mut data = state(200, 100);
+        data.is_selecting = true;
+        data.start_pos = Point::new(10.0, 10.0);
+        data.current_pos = Point::new(60.0, 40.0);
+        widget.end_gestures(&mut data);
+        assert!(!data.is_selecting);
+        assert_eq!(data.selection_rect, Some(Rect::new(10.0, 10.0, 60.0, 40.0)));
+
+        // 描套索时：按已描的路径闭合
+        let mut data = state(200, 100);
+        widget.tracing_lasso = true;
+        data.lasso = vec![Point::new(10.0, 10.0), Point::new(80.0, 10.0), Point::new(40.0, 60.0)];
+        widget.end_gestures(&mut data);
+        assert!(!widget.tracing_lasso);
+        assert_eq!(data.selection_rect, Some(Rect::new(10.0, 10.0, 80.0, 60.0)));
+
+        // 画标注时：保留已画的部分
+        let mut data = state(200, 100);
+        let annotation = Annotation::Rect(Rect::new(5.0, 5.0, 50.0, 30.0));
+        widget.drawing = Some(annotation.clone());
+        widget.end_gestures(&mut data);
+        assert!(widget.drawing.is_none());
+        assert_eq!(data.annotations, [annotation]);
+    }
+}
@@ -0,0 +1,74 @@
+// src/pin.rs
+
+use druid::keyboard_types::Key;
+use druid::piet::{ImageFormat, InterpolationMode, PietImage};
+use druid::{
+    BoxConstraints, Color, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    RenderContext, Size, UpdateCtx, Widget, WindowDesc,
+};
+use image::{ImageBuffer, Rgba};
+
+/// 创建把 `image` 钉在屏幕上的窗口：无边框、始终置顶，左上角位于虚拟桌面的 `position`
+///
+/// `position` 是逻辑坐标；`scale` 为显示器缩放比例，窗口按它换算成逻辑大小，使图像按原像素显示。
+pub fn pin_window<T: Data>(image: ImageBuffer<Rgba<u8>, Vec<u8>>, position: Point, scale: f64) -> WindowDesc<T> {
+    let size = Size::new(image.width() as f64 / scale, image.height() as f64 / scale);
+    WindowDesc::new(PinnedImage { image, cached_image: None })
+        .set_position(position)
+        .window_size(size)
+        .show_titlebar(false)
+        .resizable(false)
+        .set_always_on_top(true)
+}
+
+/// 钉在屏幕上的图片。按住左键可以拖动窗口，Esc 关闭
+struct PinnedImage {
+    image: ImageBuffer<Rgba<u8>, Vec<u8>>, // 要显示的图像
+    cached_image: Option<PietImage>,       // 缓存的绘制用图像
+}
+
+impl<T: Data> Widget<T> for PinnedImage {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut T, _env: &Env) {
+        match event {
+            Event::WindowConnected => ctx.request_focus(),
+            // 没有标题栏，由控件代替标题栏拖动窗口
+            Event::MouseDown(e) if e.button.is_left() => ctx.window().handle_titlebar(true),
+            Event::KeyDown(k) if k.key == Key::Escape => {
+                ctx.submit_command(druid::commands::CLOSE_WINDOW);
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &T, _env: &Env) {
+        // 注册焦点，以便接收键盘事件
+        if let LifeCycle::BuildFocusChain = event {
+            ctx.register_for_focus();
+        }
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old: &T, _data: &T, _env: &Env) {}
+
+    /// 铺满窗口，窗口大小在创建时已按缩放比例确定
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, _env: &Env) -> Size {
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, _env: &Env) {
+        let rect = ctx.size().to_rect();
+        if self.cached_image.is_none() {
+            let (w, h) = self.image.dimensions();
+            match ctx.make_image(w as usize, h as usize, self.image.as_raw(), ImageFormat::RgbaSeparate) {
+                Ok(img) => self.cached_image = Some(img),
+                Err(e) => eprintln!("生成钉图图像失败: {}", e),
+            }
+        }
+        match &self.cached_image {
+            Some(img) => ctx.draw_image(img, rect, InterpolationMode::NearestNeighbor),
+            None => ctx.fill(rect, &Color::grey8(32)),
+        }
+        // 细边框，让钉图和下面的内容区分开
+        ctx.stroke(rect.inset(-0.5), &Color::rgba8(0, 0, 0, 96), 1.0);
+    }
+}
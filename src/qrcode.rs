@@ -1,30 +1,126 @@
 // src/qrcode.rs
 
 use anyhow::Result;
-use image::{ImageBuffer, Rgba};
+use image::{DynamicImage, ImageBuffer, Rgba};
+
+/// 生成二维码时每个模块（黑白小方块）的边长（像素）
+const QR_MODULE_SIZE: u32 = 8;
+/// 二维码四周留白的宽度（模块数），规范要求至少 4 个模块
+const QR_QUIET_ZONE: u32 = 4;
 
 /// 接收一个 RGBA 图像，并尝试扫描其中的二维码。
 ///
 /// # 返回
-/// - `Ok(Some(String))`: 成功扫描到二维码，并返回其内容。
+/// - `Ok(Some(String))`: 成功扫描到二维码，并返回其内容（有多个时返回第一个）。
 /// - `Ok(None)`: 图像中未找到可识别的二维码。
 /// - `Err(e)`: 在扫描过程中发生错误。
-pub fn scan_qr_code(image_buffer: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<Option<String>> {
+///
+/// 图像按值传入：调用方通常刚裁剪出一张新图，扫描时直接使用它，不再复制一份像素数据
+/// （4K 选区时约 30 MB）。还要继续使用原图时请改用 [`scan_all_codes`]。
+pub fn scan_qr_code(image_buffer: ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<Option<String>> {
+    Ok(scan_all_codes(image_buffer)?.into_iter().next())
+}
+
+/// 扫描图像中的所有二维码，返回每个成功解码的内容。
+///
+/// 结果按解码器找到它们的顺序排列，同一张图每次的顺序相同；内容重复的只保留一个。
+/// 如果一个都没有识别到，会把图像反色后再试一次，
+/// 以兼容深色背景上的浅色二维码（例如暗色主题界面）。
+pub fn scan_all_codes(image_buffer: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<Vec<String>> {
+    // bardecoder 需要 image crate 的 `DynamicImage` 类型。解码器本来就只看灰度，
+    // 这里直接转成灰度图，只需要原图四分之一大小的副本，反色重试也在它上面进行
+    let mut image = DynamicImage::ImageLuma8(image::imageops::grayscale(image_buffer));
+
+    let codes = decode_all(&image);
+    if !codes.is_empty() {
+        return Ok(codes);
+    }
+
+    // 反色后重试
+    image.invert();
+    Ok(decode_all(&image))
+}
+
+/// 解码图像并返回所有成功解码的内容
+fn decode_all(image: &DynamicImage) -> Vec<String> {
     // 创建一个解码器实例
     let decoder = bardecoder::default_decoder();
 
-    // bardecoder 需要 image crate 的 `DynamicImage` 类型
-    let image = image::DynamicImage::ImageRgba8(image_buffer.clone());
-
     // 解码图像。decode 方法返回一个结果的向量，因为一张图里可能有多个码
-    let results = decoder.decode(&image);
+    let mut codes: Vec<String> = Vec::new();
+    // `filter_map` 会过滤掉 Err 并解包 Ok
+    for text in decoder.decode(image).into_iter().filter_map(Result::ok) {
+        if !codes.contains(&text) {
+            codes.push(text);
+        }
+    }
+    codes
+}
+
+/// 把文本编码为二维码图像，四周带有留白。
+///
+/// # 返回
+/// - `Ok(image)`: 黑色模块、白色背景的 RGBA 图像。
+/// - `Err(e)`: 文本过长等原因无法编码。
+pub fn generate_qr(text: &str) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    // 依赖的 `qrcode` crate 与本模块同名，用 `::` 指明
+    let code = ::qrcode::QrCode::new(text.as_bytes())?;
+    let width = code.width() as u32;
+    let colors = code.to_colors();
+
+    let side = (width + 2 * QR_QUIET_ZONE) * QR_MODULE_SIZE;
+    let image = ImageBuffer::from_fn(side, side, |x, y| {
+        let (mx, my) = (x / QR_MODULE_SIZE, y / QR_MODULE_SIZE);
+        let in_code = (QR_QUIET_ZONE..QR_QUIET_ZONE + width).contains(&mx)
+            && (QR_QUIET_ZONE..QR_QUIET_ZONE + width).contains(&my);
+        let dark = in_code && {
+            let index = (my - QR_QUIET_ZONE) * width + (mx - QR_QUIET_ZONE);
+            colors[index as usize] == ::qrcode::Color::Dark
+        };
+        if dark {
+            Rgba([0, 0, 0, 255])
+        } else {
+            Rgba([255, 255, 255, 255])
+        }
+    });
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_code_round_trips() {
+        for text in ["rsqs", "https://example.com/?q=rsqs&n=1"] {
+            assert_eq!(scan_qr_code(generate_qr(text).unwrap()).unwrap(), Some(text.to_string()));
+        }
+    }
+
+    #[test]
+    fn scans_inverted_code() {
+        // 深色背景上的浅色二维码，第一次解码失败后靠反色重试识别
+        let mut image = generate_qr("rsqs inverted").unwrap();
+        image::imageops::invert(&mut image);
+        assert_eq!(scan_qr_code(image).unwrap(), Some("rsqs inverted".to_string()));
+    }
 
-    // 我们只需要找到第一个成功解码的内容即可
-    let first_decoded_text = results
-        .into_iter()
-        // `filter_map` 会过滤掉 Err 并解包 Ok
-        .filter_map(Result::ok)
-        .next();
+    #[test]
+    fn scans_every_code_in_a_stable_order() {
+        // 两个二维码左右并排，各自带有留白
+        let (left, right) = (generate_qr("first").unwrap(), generate_qr("second").unwrap());
+        let mut image = ImageBuffer::from_pixel(
+            left.width() + right.width(),
+            left.height().max(right.height()),
+            Rgba([255, 255, 255, 255]),
+        );
+        image::imageops::overlay(&mut image, &left, 0, 0);
+        image::imageops::overlay(&mut image, &right, left.width() as i64, 0);
 
-    Ok(first_decoded_text)
-}
\ No newline at end of file
+        let codes = scan_all_codes(&image).unwrap();
+        let mut sorted = codes.clone();
+        sorted.sort();
+        assert_eq!(sorted, ["first", "second"]);
+        assert_eq!(scan_all_codes(&image).unwrap(), codes);
+    }
+}
@@ -0,0 +1,94 @@
+// src/redact.rs
+
+use druid::Rect;
+use image::{ImageBuffer, Rgba};
+
+/// 默认的马赛克块大小（像素）
+pub const DEFAULT_BLOCK: u32 = 10;
+/// 马赛克块大小的调整范围（像素）
+pub const MIN_BLOCK: u32 = 2;
+pub const MAX_BLOCK: u32 = 64;
+
+/// 对图像中的 `region`（像素坐标）打马赛克：每 `block` x `block` 的块取平均色后填满整块
+///
+/// 块从 `region` 的左上角开始划分，`region` 超出图像的部分会被忽略，
+/// 所以同一区域无论在整张截图还是裁剪后的图像上处理，块的划分都一致。
+pub fn apply_pixelation(img: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, region: Rect, block: u32) {
+    let (w, h) = img.dimensions();
+    let region = region.abs().round();
+    let (rx0, ry0, rx1, ry1) = (region.x0 as i64, region.y0 as i64, region.x1 as i64, region.y1 as i64);
+    let block = block.max(1) as i64;
+
+    for by in (ry0..ry1).step_by(block as usize) {
+        for bx in (rx0..rx1).step_by(block as usize) {
+            // 块与区域、图像的交集
+            let x0 = bx.max(0) as u32;
+            let y0 = by.max(0) as u32;
+            let x1 = (bx + block).min(rx1).clamp(0, w as i64) as u32;
+            let y1 = (by + block).min(ry1).clamp(0, h as i64) as u32;
+            if x0 >= x1 || y0 >= y1 {
+                continue;
+            }
+
+            let mut sum = [0u64; 4];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    for (s, c) in sum.iter_mut().zip(img.get_pixel(x, y).0) {
+                        *s += c as u64;
+                    }
+                }
+            }
+            let count = ((x1 - x0) * (y1 - y0)) as u64;
+            let average = Rgba(sum.map(|s| ((s + count / 2) / count) as u8));
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    img.put_pixel(x, y, average);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每个像素颜色都不同的测试图像
+    fn image() -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(8, 8, |x, y| Rgba([(x * 30) as u8, (y * 30) as u8, ((x + y) * 10) as u8, 255]))
+    }
+
+    #[test]
+    fn redacted_region_no_longer_matches() {
+        let original = image();
+        let mut redacted = original.clone();
+        apply_pixelation(&mut redacted, Rect::new(2.0, 2.0, 6.0, 6.0), 2);
+
+        let changed = (2..6)
+            .flat_map(|y| (2..6).map(move |x| (x, y)))
+            .filter(|&(x, y)| redacted.get_pixel(x, y) != original.get_pixel(x, y))
+            .count();
+        assert!(changed > 0);
+        // 每个 2x2 块都是同一个颜色
+        for (bx, by) in [(2, 2), (4, 2), (2, 4), (4, 4)] {
+            let block = redacted.get_pixel(bx, by);
+            assert_eq!(redacted.get_pixel(bx + 1, by), block);
+            assert_eq!(redacted.get_pixel(bx, by + 1), block);
+            assert_eq!(redacted.get_pixel(bx + 1, by + 1), block);
+        }
+    }
+
+    #[test]
+    fn pixels_outside_region_are_untouched() {
+        let original = image();
+        let mut redacted = original.clone();
+        // 区域超出图像右下角，超出部分被忽略
+        apply_pixelation(&mut redacted, Rect::new(5.0, 5.0, 12.0, 12.0), 4);
+        for (x, y, pixel) in redacted.enumerate_pixels() {
+            if x < 5 || y < 5 {
+                assert_eq!(pixel, original.get_pixel(x, y), "({}, {})", x, y);
+            }
+        }
+        assert_ne!(redacted.get_pixel(7, 7), original.get_pixel(7, 7));
+    }
+}
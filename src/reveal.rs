@@ -0,0 +1,32 @@
+// src/reveal.rs
+
+use anyhow::Result;
+use std::path::Path;
+use std::process::Command;
+
+/// 在系统文件管理器中打开文件所在的文件夹。
+///
+/// Windows 和 macOS 会同时选中该文件；其他平台通过 `xdg-open` 打开所在目录。
+pub fn reveal_in_file_manager(path: &Path) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // explorer 要求路径紧跟在 `/select,` 之后，不能整体加引号
+        Command::new("explorer")
+            .raw_arg(format!("/select,\"{}\"", path.display()))
+            .spawn()?;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        Command::new("open").arg("-R").arg(path).spawn()?;
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        Command::new("xdg-open").arg(dir).spawn()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+// src/save.rs
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Local};
+use image::buffer::ConvertBuffer;
+use image::{ImageBuffer, ImageFormat, Rgb, Rgba};
+
+/// 保存对话框中提供的格式：名称和扩展名
+pub const SAVE_FORMATS: [(&str, &[&str]); 4] = [
+    ("PNG", &["png"]),
+    ("JPEG", &["jpg", "jpeg"]),
+    ("WebP", &["webp"]),
+    ("BMP", &["bmp"]),
+];
+
+/// 按文件扩展名选择格式保存图像。
+///
+/// 支持 PNG、JPEG、WebP、BMP；JPEG 不支持透明通道，会先转换为 RGB。
+pub fn save_image(img: &ImageBuffer<Rgba<u8>, Vec<u8>>, path: &Path) -> Result<()> {
+    let format = ImageFormat::from_path(path)
+        .map_err(|_| anyhow!("无法根据扩展名确定图片格式: {}", path.display()))?;
+    match format {
+        ImageFormat::Jpeg => {
+            let rgb: ImageBuffer<Rgb<u8>, Vec<u8>> = img.convert();
+            rgb.save_with_format(path, format)?;
+        }
+        ImageFormat::Png | ImageFormat::WebP | ImageFormat::Bmp => img.save_with_format(path, format)?,
+        other => bail!("不支持保存为 {:?} 格式", other),
+    }
+    Ok(())
+}
+
+/// 快速保存时默认的文件名模板，展开后例如 `Screenshot_2025-01-31_08-30-00`
+pub const DEFAULT_NAME_TEMPLATE: &str = "Screenshot_{date}_{time}";
+
+/// 展开文件名模板时使用的值
+pub struct NameFields<'a> {
+    pub time: DateTime<Local>, // {date} 和 {time}
+    pub app: &'a str,          // {app}：截图时位于前台的程序名，不知道时为空
+    pub width: u32,            // {w}：图片宽度（像素）
+    pub height: u32,           // {h}：图片高度（像素）
+}
+
+impl<'a> NameFields<'a> {
+    /// 以当前时间命名大小为 `width` x `height` 的图片
+    pub fn now(app: &'a str, width: u32, height: u32) -> Self {
+        NameFields { time: Local::now(), app, width, height }
+    }
+}
+
+/// 展开文件名模板（不含扩展名），`{counter}` 替换为 `counter`
+///
+/// 可用的标记：`{date}`（`2025-01-31`）、`{time}`（`08-30-00`）、`{app}`、`{w}`、`{h}`、`{counter}`。
+/// 标记的值中不能用在文件名里的字符替换为 `_`；模板本身可以含 `/`，即保存到图片文件夹下的子文件夹。
+/// 遇到未知或缺少 `}` 的标记时返回错误。
+pub fn expand_name(template: &str, fields: &NameFields, counter: u32) -> Result<String> {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let token = &rest[start + 1..];
+        let end = token.find('}').ok_or_else(|| anyhow!("文件名模板 \"{}\" 中的 {{ 缺少对应的 }}", template))?;
+        let value = match &token[..end] {
+            "date" => fields.time.format("%Y-%m-%d").to_string(),
+            "time" => fields.time.format("%H-%M-%S").to_string(),
+            "app" if fields.app.trim().is_empty() => "unknown".to_string(),
+            "app" => fields.app.trim().to_string(),
+            "w" => fields.width.to_string(),
+            "h" => fields.height.to_string(),
+            "counter" => counter.to_string(),
+            other => bail!(
+                "文件名模板中有未知的标记 {{{}}}，可用的有 {{date}} {{time}} {{app}} {{w}} {{h}} {{counter}}",
+                other
+            ),
+        };
+        name.extend(value.chars().map(|c| if "<>:\"/\\|?*".contains(c) || c.is_control() { '_' } else { c }));
+        rest = &token[end + 1..];
+    }
+    name.push_str(rest);
+    if name.trim().is_empty() {
+        bail!("文件名模板 \"{}\" 展开后为空", template);
+    }
+    Ok(name)
+}
+
+/// 检查文件名模板能否展开，用于启动时提前发现配置错误
+pub fn check_name_template(template: &str) -> Result<()> {
+    expand_name(template, &NameFields::now("", 1, 1), 1).map(drop)
+}
+
+/// 图片文件夹中按 `template` 命名的 PNG 文件，不会覆盖已有的文件
+///
+/// 找不到图片文件夹时返回 `Ok(None)`，模板有误时返回错误。
+pub fn default_save_path(template: &str, fields: &NameFields) -> Result<Option<PathBuf>> {
+    let Some(dir) = dirs::picture_dir() else {
+        return Ok(None);
+    };
+    unused_path(&dir, template, fields, "png").map(Some)
+}
+
+/// `dir` 下按 `template` 命名、还不存在的文件
+///
+/// 模板含 `{counter}` 时从 1 开始递增它，直到没有同名文件；否则交给 [`unique_path`] 加上序号。
+fn unused_path(dir: &Path, template: &str, fields: &NameFields, ext: &str) -> Result<PathBuf> {
+    if !template.contains("{counter}") {
+        return Ok(unique_path(dir, &expand_name(template, fields, 1)?, ext));
+    }
+    let mut counter = 1;
+    loop {
+        let path = dir.join(format!("{}.{}", expand_name(template, fields, counter)?, ext));
+        if !path.exists() {
+            return Ok(path);
+        }
+        counter += 1;
+    }
+}
+
+/// `dir` 下名为 `stem.ext` 的路径；已有同名文件时（例如同一秒内截了两次）
+/// 依次尝试 `stem_2.ext`、`stem_3.ext`……，不会覆盖之前的文件
+fn unique_path(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.{}", stem, ext));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}_{}.{}", stem, n, ext));
+        n += 1;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_path_does_not_overwrite() {
+        let dir = std::env::temp_dir().join(format!("rsqs-unique-path-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let first = unique_path(&dir, "Screenshot", "png");
+        assert_eq!(first, dir.join("Screenshot.png"));
+        std::fs::write(&first, b"").unwrap();
+        let second = unique_path(&dir, "Screenshot", "png");
+        assert_eq!(second, dir.join("Screenshot_2.png"));
+        std::fs::write(&second, b"").unwrap();
+        assert_eq!(unique_path(&dir, "Screenshot", "png"), dir.join("Screenshot_3.png"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 2025-01-31 08:30:00 截取的 640x480 图片
+    fn fields(app: &str) -> NameFields<'_> {
+        let time = chrono::NaiveDate::from_ymd_opt(2025, 1, 31).unwrap().and_hms_opt(8, 30, 0).unwrap();
+        NameFields { time: time.and_local_timezone(Local).unwrap(), app, width: 640, height: 480 }
+    }
+
+    #[test]
+    fn expands_every_token() {
+        let name = expand_name("{app}/{date}_{time}_{w}x{h}_{counter}", &fields("Firefox"), 3).unwrap();
+        assert_eq!(name, "Firefox/2025-01-31_08-30-00_640x480_3");
+        assert_eq!(expand_name(DEFAULT_NAME_TEMPLATE, &fields(""), 1).unwrap(), "Screenshot_2025-01-31_08-30-00");
+        // 程序名中的路径分隔符不会变成子文件夹
+        assert_eq!(expand_name("{app}", &fields("a/b: c"), 1).unwrap(), "a_b_ c");
+        assert_eq!(expand_name("{app}", &fields(" "), 1).unwrap(), "unknown");
+    }
+
+    #[test]
+    fn rejects_unknown_or_unclosed_tokens() {
+        assert!(expand_name("{year}", &fields(""), 1).is_err());
+        assert!(expand_name("shot_{date", &fields(""), 1).is_err());
+        assert!(expand_name("", &fields(""), 1).is_err());
+        assert!(check_name_template("{app}_{counter}").is_ok());
+    }
+
+    #[test]
+    fn counter_skips_existing_files() {
+        let dir = std::env::temp_dir().join(format!("rsqs-counter-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("shot_1.png"), b"").unwrap();
+        std::fs::write(dir.join("shot_2.png"), b"").unwrap();
+        let path = unused_path(&dir, "shot_{counter}", &fields(""), "png").unwrap();
+        assert_eq!(path, dir.join("shot_3.png"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
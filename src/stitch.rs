@@ -0,0 +1,222 @@
+// src/stitch.rs
+
+use std::ops::Range;
+
+use anyhow::{Result, anyhow, bail};
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+/// 用于匹配的行条高度（像素）
+const STRIP_HEIGHT: u32 = 32;
+/// 认为两帧重叠所需的最低相关系数
+const MIN_CORRELATION: f64 = 0.9;
+/// 匹配时最多取样的列数，列再多只会变慢，不会更准
+const MAX_SAMPLE_COLUMNS: u32 = 512;
+
+/// 把依次滚动截取的多帧画面纵向拼接成一张长图。
+///
+/// 顶部和底部在各帧之间完全不变的行（标题栏、工具栏、状态栏等）视为固定区域，
+/// 只在长图的开头和结尾各出现一次；中间滚动的部分通过行条的归一化互相关（NCC）
+/// 找出相邻两帧重叠的行数，重叠部分只保留一份。
+/// 没有滚动的帧会被跳过，找不到重叠时整段接在后面。所有帧必须同样大小。
+pub fn stitch_vertical(frames: &[DynamicImage]) -> Result<DynamicImage> {
+    let (first, rest) = frames.split_first().ok_or_else(|| anyhow!("没有可拼接的画面"))?;
+    let (w, h) = first.dimensions();
+    if let Some(frame) = rest.iter().find(|f| f.dimensions() != (w, h)) {
+        bail!("画面尺寸不一致: {}x{} 与 {}x{}", w, h, frame.width(), frame.height());
+    }
+
+    // 滚动的区域为 [band.start, band.end)
+    let band = scrolling_band(frames);
+    let band_height = band.end - band.start;
+
+    // 每一段为 (帧, 起始行, 行数)；第一帧保留固定的顶部和滚动区域
+    let mut pieces = vec![(first, 0, band.end)];
+    let mut last = first;
+    let mut prev = Samples::new(first, band.clone());
+    for frame in rest {
+        let next = Samples::new(frame, band.clone());
+        match scroll_offset(&prev, &next) {
+            Some(0) => continue,
+            Some(dy) => pieces.push((frame, band.end - dy, dy)),
+            None => pieces.push((frame, band.start, band_height)),
+        }
+        prev = next;
+        last = frame;
+    }
+    // 固定的底部取自最后一帧
+    pieces.push((last, band.end, h - band.end));
+
+    let total = pieces.iter().map(|&(_, _, rows)| rows).sum();
+    let mut stitched = RgbaImage::new(w, total);
+    let mut y = 0;
+    for (frame, top, rows) in pieces.into_iter().filter(|&(_, _, rows)| rows > 0) {
+        let part = frame.crop_imm(0, top, w, rows).to_rgba8();
+        image::imageops::overlay(&mut stitched, &part, 0, y as i64);
+        y += rows;
+    }
+    Ok(DynamicImage::ImageRgba8(stitched))
+}
+
+/// 各帧中随滚动变化的行的范围，上方和下方是所有帧都完全相同的固定区域
+///
+/// 只比较内容有变化的相邻两帧；固定区域太大、剩下的部分不够匹配时不做区分，返回整帧。
+fn scrolling_band(frames: &[DynamicImage]) -> Range<u32> {
+    let h = frames.first().map_or(0, |f| f.height());
+    let rows = |frame: &DynamicImage| {
+        let bytes = frame.as_bytes();
+        let stride = bytes.len() / h.max(1) as usize;
+        bytes.chunks_exact(stride.max(1)).collect::<Vec<_>>()
+    };
+    let (mut top, mut bottom) = (h, h);
+    for pair in frames.windows(2) {
+        let (a, b) = (rows(&pair[0]), rows(&pair[1]));
+        if a == b {
+            continue;
+        }
+        let same_top = a.iter().zip(&b).take_while(|(x, y)| x == y).count() as u32;
+        let same_bottom = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count() as u32;
+        top = top.min(same_top);
+        bottom = bottom.min(same_bottom);
+    }
+    if top == h || top + bottom + STRIP_HEIGHT * 2 > h {
+        return 0..h;
+    }
+    top..h - bottom
+}
+
+/// 按列取样的灰度画面
+struct Samples {
+    values: Vec<f64>, // 按行存放的灰度值
+    columns: usize,   // 每行取样的列数
+    rows: u32,
+}
+
+impl Samples {
+    /// 取样 `frame` 中 `rows` 范围内的行
+    fn new(frame: &DynamicImage, rows: Range<u32>) -> Self {
+        let gray = frame.to_luma8();
+        let w = gray.width();
+        let step = w.div_ceil(MAX_SAMPLE_COLUMNS).max(1);
+        let height = rows.end - rows.start;
+        let values: Vec<f64> = rows
+            .flat_map(|y| (0..w).step_by(step as usize).map(move |x| (x, y)))
+            .map(|(x, y)| gray.get_pixel(x, y).0[0] as f64)
+            .collect();
+        let columns = values.len() / height.max(1) as usize;
+        Samples { values, columns, rows: height }
+    }
+
+    /// 从第 `top` 行开始、高 `STRIP_HEIGHT` 的行条
+    fn strip(&self, top: u32) -> &[f64] {
+        let start = top as usize * self.columns;
+        &self.values[start..start + STRIP_HEIGHT as usize * self.columns]
+    }
+}
+
+/// 两个行条的归一化互相关系数；任一行条没有变化（纯色）时返回 `None`
+fn correlation(a: &[f64], b: &[f64]) -> Option<f64> {
+    let n = a.len() as f64;
+    let (mean_a, mean_b) = (a.iter().sum::<f64>() / n, b.iter().sum::<f64>() / n);
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        let (dx, dy) = (x - mean_a, y - mean_b);
+        cov += dx * dy;
+        var_a += dx * dx;
+        var_b += dy * dy;
+    }
+    (var_a > f64::EPSILON && var_b > f64::EPSILON).then(|| cov / (var_a * var_b).sqrt())
+}
+
+/// 求 `next` 相对 `prev` 向上滚动的行数：`next` 的第 r 行对应 `prev` 的第 r + dy 行
+///
+/// 在 `next` 上半部分选变化最大的行条作为模板（纯色区域无法定位），
+/// 再在 `prev` 中找相关系数最高的位置；最高值不够高时返回 `None`。
+fn scroll_offset(prev: &Samples, next: &Samples) -> Option<u32> {
+    let h = next.rows;
+    if h < STRIP_HEIGHT * 2 {
+        return None;
+    }
+    let variance = |strip: &[f64]| {
+        let mean = strip.iter().sum::<f64>() / strip.len() as f64;
+        strip.iter().map(|v| (v - mean).powi(2)).sum::<f64>()
+    };
+    let top = (0..h / 2)
+        .step_by(STRIP_HEIGHT as usize / 2)
+        .max_by(|&a, &b| variance(next.strip(a)).total_cmp(&variance(next.strip(b))))?;
+    let template = next.strip(top);
+
+    // 相关系数相同时取较小的偏移，避免重复图案把没有滚动的帧错配到更远处
+    let (dy, best) = (0..=h - STRIP_HEIGHT - top)
+        .filter_map(|dy| correlation(prev.strip(top + dy), template).map(|c| (dy, c)))
+        .reduce(|best, c| if c.1 > best.1 { c } else { best })?;
+    (best >= MIN_CORRELATION).then_some(dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 确定性的噪声图像，每一行都各不相同
+    fn noise(width: u32, height: u32, seed: u32) -> RgbaImage {
+        let mut state = seed.wrapping_mul(2654435761).max(1);
+        RgbaImage::from_fn(width, height, |_, _| {
+            // xorshift32
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let [r, g, b, _] = state.to_le_bytes();
+            image::Rgba([r, g, b, 255])
+        })
+    }
+
+    /// 从 `source` 的第 `top` 行开始切出高 `height` 的一帧
+    fn frame(source: &RgbaImage, top: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(image::imageops::crop_imm(source, 0, top, source.width(), height).to_image())
+    }
+
+    #[test]
+    fn stitches_overlapping_frames_back_into_the_source() {
+        let source = noise(64, 415, 1);
+        let frames: Vec<_> = [0, 50, 110, 150, 215].iter().map(|&top| frame(&source, top, 200)).collect();
+        assert_eq!(stitch_vertical(&frames).unwrap().to_rgba8(), source);
+    }
+
+    #[test]
+    fn keeps_static_header_and_footer_once() {
+        // 每帧为固定的顶部 20 行 + 滚动的内容 + 固定的底部 15 行
+        let (header, footer, content) = (noise(64, 20, 2), noise(64, 15, 3), noise(64, 280, 4));
+        let offsets = [0, 40, 85, 115];
+        let band = 200 - 20 - 15;
+        let frames: Vec<_> = offsets
+            .iter()
+            .map(|&top| {
+                let mut f = RgbaImage::new(64, 200);
+                image::imageops::overlay(&mut f, &header, 0, 0);
+                image::imageops::overlay(&mut f, &frame(&content, top, band).to_rgba8(), 0, 20);
+                image::imageops::overlay(&mut f, &footer, 0, 185);
+                DynamicImage::ImageRgba8(f)
+            })
+            .collect();
+
+        let mut expected = RgbaImage::new(64, 20 + 115 + band + 15);
+        image::imageops::overlay(&mut expected, &header, 0, 0);
+        image::imageops::overlay(&mut expected, &frame(&content, 0, 115 + band).to_rgba8(), 0, 20);
+        image::imageops::overlay(&mut expected, &footer, 0, (20 + 115 + band) as i64);
+        assert_eq!(stitch_vertical(&frames).unwrap().to_rgba8(), expected);
+    }
+
+    #[test]
+    fn skips_frames_that_did_not_scroll() {
+        let source = noise(64, 260, 5);
+        let (a, b) = (frame(&source, 0, 200), frame(&source, 60, 200));
+        let stitched = stitch_vertical(&[a.clone(), a.clone(), b.clone(), b]).unwrap();
+        assert_eq!(stitched.to_rgba8(), source);
+    }
+
+    #[test]
+    fn rejects_mismatched_or_missing_frames() {
+        let source = noise(64, 300, 6);
+        assert!(stitch_vertical(&[frame(&source, 0, 200), frame(&source, 50, 180)]).is_err());
+        assert!(stitch_vertical(&[]).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+// tests/lib_smoke.rs
+//
+// 只通过根模块重新导出的函数使用本库，确认 readme 中的用法可以编译并得到预期结果。
+
+use druid::Rect;
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+
+/// 每个像素记录自己的坐标：`[x, y, 0, 255]`
+fn synthetic(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| Rgba([x as u8, y as u8, 0, 255])))
+}
+
+#[test]
+fn crops_synthetic_image() {
+    let screen = synthetic(64, 48);
+    let region = rsqs::crop(&screen, Rect::new(10.0, 5.0, 30.0, 25.0));
+    assert_eq!(region.dimensions(), (20, 20));
+    assert_eq!(region.get_pixel(0, 0), &Rgba([10, 5, 0, 255]));
+    assert_eq!(region.get_pixel(19, 19), &Rgba([29, 24, 0, 255]));
+}
+
+#[test]
+fn crop_is_clipped_to_image() {
+    let screen = synthetic(64, 48);
+    let region = rsqs::crop(&screen, Rect::new(50.0, 40.0, 100.0, 100.0));
+    assert_eq!(region.dimensions(), (14, 8));
+    assert_eq!(region.get_pixel(13, 7), &screen.get_pixel(63, 47));
+    assert_eq!(rsqs::crop(&screen, Rect::new(100.0, 100.0, 120.0, 120.0)).dimensions(), (0, 0));
+}
+
+#[test]
+fn cropped_region_without_code_scans_to_none() {
+    let region = rsqs::crop(&synthetic(64, 48), Rect::new(0.0, 0.0, 32.0, 32.0));
+    assert_eq!(rsqs::scan_qr_code(region).unwrap(), None);
+}